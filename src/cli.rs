@@ -0,0 +1,106 @@
+use std::path::PathBuf;
+use std::process;
+use clap::Parser;
+use ecosystem_simulation::utilities::simulation_parameters::SimParams;
+use crate::headless::DEFAULT_STEPS;
+
+#[derive(Parser)]
+#[command(name = "EcoSim", about = "Simulates an evolving ecosystem")]
+pub struct Args{
+    /// Run without a window, stepping the world as fast as possible
+    #[arg(long)]
+    pub headless: bool,
+    /// Number of steps to run in headless mode
+    #[arg(long, default_value_t = DEFAULT_STEPS)]
+    pub steps: i32,
+    /// Width and height of the world
+    #[arg(long)]
+    pub size: Option<f32>,
+    /// World seed, taken from the config or the default seed if not given
+    #[arg(long, conflicts_with = "random_seed")]
+    pub seed: Option<u64>,
+    /// Start from a random world seed instead
+    #[arg(long)]
+    pub random_seed: bool,
+    /// Autosave interval in simulated minutes
+    #[arg(long, value_parser = clap::value_parser!(i32).range(1..))]
+    pub autosave: Option<i32>,
+    /// Number of autosaves to keep before the oldest is deleted
+    #[arg(long)]
+    pub autosave_keep: Option<usize>,
+    /// Continue a headless run from the newest autosave in the output directory
+    #[arg(long)]
+    pub resume: bool,
+    /// Re-run a save from its start using its event log and check that it plays out the same
+    #[arg(long)]
+    pub replay: Option<PathBuf>,
+    /// Export statistics as CSV and JSON every this many simulated minutes in headless mode
+    #[arg(long)]
+    pub export_interval: Option<i32>,
+    /// Also write the final save of a headless run as JSON
+    #[arg(long)]
+    pub json_save: bool,
+    /// Directory that the saves and stats folders are written to
+    #[arg(long, default_value = ".")]
+    pub output: PathBuf,
+    /// JSON file of simulation parameters to start from
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+    /// Write the resolved simulation parameters to a JSON file and exit
+    #[arg(long)]
+    pub write_config: Option<PathBuf>,
+}
+
+impl Args{
+    pub fn sim_params(&self) -> SimParams{
+        let mut sim_params = match &self.config {
+            Some(path) => SimParams::load(path).unwrap_or_else(|err| {
+                eprintln!("Unable to load config {}: {}", path.display(), err);
+                process::exit(1);
+            }),
+            None => SimParams::default(),
+        };
+
+        if let Some(size) = self.size {
+            sim_params.world.width = size;
+            sim_params.world.height = size;
+        }
+        if let Some(seed) = self.seed {
+            sim_params.world.seed = seed;
+        }
+        if self.random_seed {
+            sim_params.world.seed = rand::random();
+        }
+        if let Some(autosave) = self.autosave {
+            sim_params.autosave = autosave;
+        }
+        if let Some(keep) = self.autosave_keep {
+            sim_params.autosave_keep = keep;
+        }
+
+        sim_params
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn autosave_interval_must_be_positive(){
+        assert!(Args::try_parse_from(["ecosim", "--autosave", "0"]).is_err());
+        assert!(Args::try_parse_from(["ecosim", "--autosave", "-5"]).is_err());
+        let args = Args::try_parse_from(["ecosim", "--autosave", "1"]).unwrap();
+        assert_eq!(args.sim_params().autosave, 1);
+    }
+
+    #[test]
+    fn config_autosave_interval_is_clamped(){
+        let path = std::env::temp_dir().join("ecosim_cli_autosave_config.json");
+        SimParams{ autosave: 0, ..SimParams::default() }.save(&path).unwrap();
+        let args = Args::try_parse_from(["ecosim", "--config", path.to_str().unwrap()]).unwrap();
+        let autosave = args.sim_params().autosave;
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(autosave, 1);
+    }
+}
@@ -5,21 +5,32 @@ use rand::Rng;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use crate::environment::eggs::Eggs;
-use crate::environment::neural_network::Network;
+use crate::environment::energy::{EnergyFlows, Flow, FlowKind, Food};
+use crate::environment::genome::{Gene, Genome};
+use crate::environment::lineage::Lineage;
+use crate::environment::neural_network::{Innovations, Network};
 use crate::environment::plants::Plants;
 use crate::environment::collisions::{CELL_SIZE, Collisions, DIV};
 use crate::environment::fruit::Fruits;
 use crate::environment::rocks::RockMap;
+use crate::utilities::random::{SimRng, Stream, stream};
 use crate::utilities::simulation_parameters::SimParams;
 use crate::environment::species::SpeciesList;
 use crate::environment::temperature::TemperatureMap;
 use crate::environment::vision::SensoryInput;
 use crate::rendering::instance::Instance;
 
+pub const MEMORY_CELLS: usize = 2;
+
 #[derive(Clone,Serialize,Deserialize)]
 pub struct Animal{
     pub id: usize,
     pub generation: usize,
+    #[serde(default)]
+    pub parent_id: Option<usize>,
+    //the other parent when reproduction is sexual
+    #[serde(default)]
+    pub mate_id: Option<usize>,
     pub species_id: usize,
     pub maturity: f32,
     pub lean_mass: f32,
@@ -28,66 +39,135 @@ pub struct Animal{
     pub body: Instance,
     pub brain: Brain,
     pub senses: SensoryInput,
-    max_stats: MaxStats,
+    pub genome: Genome,
     pub reproduction_stats: ReproductionStats,
     pub combat_stats: CombatStats,
     pub age: f32,
     pub temperature: f32,
     pub temp_tolerance: f32,
-    pub ideal_temp: f32
+    pub ideal_temp: f32,
+    #[serde(default)]
+    pub offspring_count: usize,
+    #[serde(default)]
+    pub death_cause: Option<DeathCause>,
+}
+
+#[derive(Clone,Copy,PartialEq,Eq,Debug,Serialize,Deserialize)]
+pub enum DeathCause{
+    Predation,
+    Starvation,
+    Temperature,
+    OldAge,
+}
+impl DeathCause{
+    pub const ALL: [DeathCause;4] = [DeathCause::Predation, DeathCause::Starvation, DeathCause::Temperature, DeathCause::OldAge];
+
+    pub fn name(&self) -> &'static str{
+        match self {
+            DeathCause::Predation => "Predation",
+            DeathCause::Starvation => "Starvation",
+            DeathCause::Temperature => "Temperature",
+            DeathCause::OldAge => "Old age",
+        }
+    }
+}
+
+//what is left of an animal once it has been removed from the world
+#[derive(Clone,Serialize,Deserialize)]
+pub struct Death{
+    #[serde(default)]
+    pub id: usize,
+    pub cause: DeathCause,
+    pub age: f32,
+    pub offspring: usize,
 }
 
 impl Animal{
-    fn offspring(&self, sim_params: &SimParams,species_list: &mut SpeciesList) ->Self{
-        let mut new_animal = self.clone();
+    fn offspring(&self, mate: Option<&Animal>, sim_params: &SimParams,species_list: &mut SpeciesList, innovations: &mut Innovations, rng: &mut SimRng) ->Self{
+        let mut new_animal = match mate {
+            Some(mate) => self.crossover(mate, rng),
+            None => self.clone(),
+        };
 
-        let mut rng = rand::thread_rng();
         let mutation_strength = sim_params.animals.physical_mutation_strength/100.;
         let mutation_rate = (sim_params.animals.physical_mutation_rate/100.) as f64;
 
         new_animal.maturity = 0.;
         new_animal.generation = self.generation+1;
+        new_animal.parent_id = Some(self.id);
+        new_animal.mate_id = mate.map(|mate| mate.id);
         new_animal.age = 0.;
+        new_animal.offspring_count = 0;
+        new_animal.death_cause = None;
+        new_animal.brain.memory = vec![0.0; MEMORY_CELLS];
         new_animal.resources.protein = new_animal.lean_mass*0.2*(new_animal.reproduction_stats.offspring_investment);
         new_animal.resources.energy = (new_animal.reproduction_stats.offspring_investment+0.5)*new_animal.lean_mass*5.0;
 
-        new_animal.brain.network.mutate(sim_params.animals.brain_mutation_strength/100.,sim_params.animals.brain_mutation_rate/100.);
-
-        if rng.gen_bool(mutation_rate){ new_animal.max_stats.speed = (new_animal.max_stats.speed + 3.0 * rng.gen_range(-mutation_strength..=mutation_strength)).clamp(1., 4.);}
-        if rng.gen_bool(mutation_rate) { new_animal.max_stats.attack = (new_animal.max_stats.attack + 10. * rng.gen_range(-mutation_strength..=mutation_strength)).clamp(0., 10.); }
-        if rng.gen_bool(mutation_rate) { new_animal.max_stats.size = (new_animal.max_stats.size + 0.4 * rng.gen_range(-mutation_strength..=mutation_strength)).clamp(0.16, 0.5); }
-        if rng.gen_bool(mutation_rate) { new_animal.reproduction_stats.offspring_investment = (new_animal.reproduction_stats.offspring_investment + 10. * rng.gen_range(-mutation_strength..=mutation_strength)).clamp(0., 10.); }
-        if rng.gen_bool(mutation_rate) { new_animal.combat_stats.carnivore_factor = (new_animal.combat_stats.carnivore_factor + 1.0 * rng.gen_range(-mutation_strength..=mutation_strength)).clamp(0., 1.0); }
-        if rng.gen_bool(mutation_rate) { new_animal.senses.animal_vision = (new_animal.senses.animal_vision + 12. * rng.gen_range(-mutation_strength..=mutation_strength)).clamp(0.0, 12.); }
-        if rng.gen_bool(mutation_rate) { new_animal.senses.plant_vision = (new_animal.senses.plant_vision + 12. * rng.gen_range(-mutation_strength..=mutation_strength)).clamp(0.0, 12.); }
-        if rng.gen_bool(mutation_rate) { new_animal.senses.rock_vision = (new_animal.senses.rock_vision + 12. * rng.gen_range(-mutation_strength..=mutation_strength)).clamp(0.0, 12.); }
-        if rng.gen_bool(mutation_rate) { new_animal.senses.fruit_vision = (new_animal.senses.fruit_vision + 12. * rng.gen_range(-mutation_strength..=mutation_strength)).clamp(0.0, 12.); }
-        if rng.gen_bool(mutation_rate) { new_animal.temp_tolerance = (new_animal.temp_tolerance + 14. * rng.gen_range(-mutation_strength..=mutation_strength)).clamp(1.0, 15.); }
-        if rng.gen_bool(mutation_rate) { new_animal.ideal_temp = (new_animal.ideal_temp + 35. * rng.gen_range(-mutation_strength..=mutation_strength)).clamp(0.0, 35.); }
-
-        if rng.gen_bool(mutation_rate) {
-            new_animal.hue = (new_animal.hue + rng.gen_range(-mutation_strength..=mutation_strength)).rem_euclid(1.);
-            new_animal.body.set_hsl(new_animal.hue, 1.0);
-        }
+        new_animal.brain.network.mutate(sim_params.animals.brain_mutation_strength/100.,sim_params.animals.brain_mutation_rate/100.,rng);
+        new_animal.brain.network.mutate_structure(sim_params.animals.structural_mutation_rate/100.,innovations,rng);
 
-        new_animal.combat_stats.speed = new_animal.max_stats.speed * 0.5;
-        new_animal.combat_stats.attack = new_animal.max_stats.attack * 0.5;
-        new_animal.body.scale = new_animal.max_stats.size * 0.5;
+        new_animal.genome.mutate(mutation_strength,mutation_rate,rng);
+        new_animal.express();
 
-        new_animal.lean_mass = new_animal.combat_stats.attack * 5.0 + new_animal.combat_stats.speed * 8.0 + new_animal.body.scale * 30.;
         new_animal.species_id = species_list.speciate(&new_animal,self.species_id,sim_params);
 
         new_animal
     }
+    //takes the brain topology from self, with matching brain genes and every trait gene picked from either parent
+    fn crossover(&self, mate: &Animal, rng: &mut SimRng) -> Self{
+        let mut child = self.clone();
+
+        child.brain.network = self.brain.network.crossover(&mate.brain.network, rng);
+
+        child.genome = self.genome.crossover(&mate.genome, rng);
+
+        child
+    }
+    //sets the traits an animal is born with from its genome
+    fn express(&mut self){
+        let genome = &self.genome;
+
+        self.senses = SensoryInput{
+            animal_vision: genome[Gene::AnimalVision],
+            plant_vision: genome[Gene::PlantVision],
+            fruit_vision: genome[Gene::FruitVision],
+            rock_vision: genome[Gene::RockVision],
+        };
+        self.reproduction_stats.offspring_investment = genome[Gene::OffspringInvestment];
+        self.combat_stats.carnivore_factor = genome[Gene::CarnivoreFactor];
+        self.combat_stats.speed = genome[Gene::Speed] * 0.5;
+        self.combat_stats.attack = genome[Gene::Attack] * 0.5;
+        self.body.scale = genome[Gene::Size] * 0.5;
+        self.temp_tolerance = genome[Gene::TempTolerance];
+        self.ideal_temp = genome[Gene::IdealTemp];
+        self.hue = genome[Gene::Hue];
+        self.body.set_hsl(self.hue, 1.0);
+
+        self.lean_mass = self.combat_stats.attack * 5.0 + self.combat_stats.speed * 8.0 + self.body.scale * 30.;
+    }
+    fn can_reproduce(&self, sim_params: &SimParams) -> bool{
+        self.maturity == 10. && self.reproduction_stats.birth_timer <= 0. && self.resources.energy > ((self.reproduction_stats.offspring_investment/10.)+0.5)*self.lean_mass*5.0 * sim_params.animals.reproduction_energy_cost + self.resources.max_energy * 0.3 && self.resources.protein > self.lean_mass*0.2*((self.reproduction_stats.offspring_investment/10.)+0.5) * sim_params.animals.reproduction_protein_cost
+    }
+    fn pay_reproduction_cost(&mut self, sim_params: &SimParams) -> Flow{
+        let energy = ((self.reproduction_stats.offspring_investment/10.)+0.5)*self.lean_mass*5.0 * sim_params.animals.reproduction_energy_cost;
+        let protein = self.lean_mass*0.4*((self.reproduction_stats.offspring_investment/10.)+0.5) * sim_params.animals.reproduction_protein_cost;
+
+        self.reproduction_stats.birth_timer = (10. + self.reproduction_stats.offspring_investment * 4.) * sim_params.animals.reproduction_time;
+        self.resources.energy -= energy;
+        self.resources.protein -= protein;
+
+        Flow::new(energy, protein)
+    }
     fn internal_inputs(&self, mut input: Vec<f32>) -> Vec<f32>{
         input.push(self.resources.energy/self.resources.max_energy);
         input.push(self.combat_stats.aggression);
+        input.extend_from_slice(&self.brain.memory);
       //  input.push(self.reproduction_stats.birth_desire);
 
         input
     }
 }
-#[derive(Clone,Serialize,Deserialize)]
+#[derive(Default,Clone,Serialize,Deserialize)]
 pub struct CombatStats{
     pub carnivore_factor: f32,
     pub aggression: f32,
@@ -97,20 +177,16 @@ pub struct CombatStats{
 #[derive(Clone,Serialize,Deserialize)]
 pub struct Brain{
     pub network: Network,
+    //activations of the memory outputs from the last step, fed back in as inputs
+    pub memory: Vec<f32>,
 }
-#[derive(Clone,Serialize,Deserialize)]
+#[derive(Default,Clone,Serialize,Deserialize)]
 pub struct ReproductionStats{
     pub offspring_investment: f32,
     pub birth_timer: f32,
     //pub birth_desire: f32,
 }
 #[derive(Clone,Serialize,Deserialize)]
-pub struct MaxStats{
-    speed: f32,
-    size: f32,
-    attack: f32,
-}
-#[derive(Clone,Serialize,Deserialize)]
 pub struct Resources{
     pub energy: f32,
     pub protein: f32,
@@ -119,12 +195,17 @@ pub struct Resources{
 }
 
 impl Resources{
-    fn add(&mut self,resources:(f32,f32)){
+    //returns what was actually taken in once the stores were full
+    fn add(&mut self,resources:(f32,f32)) -> Flow{
+        let (energy, protein) = (self.energy, self.protein);
+
         self.energy+=resources.0;
         self.protein+=resources.1;
 
         self.energy = self.max_energy.min(self.energy);
         self.protein = self.max_protein.min(self.protein);
+
+        Flow::new(self.energy - energy, self.protein - protein)
     }
 }
 
@@ -132,65 +213,87 @@ impl Resources{
 pub struct Animals{
     pub animals: Vec<Animal>,
     next_free_id: usize,
+    innovations: Innovations,
+    #[serde(default)]
+    lineage: Lineage,
+    //animals removed since the stats last collected them
+    #[serde(default)]
+    deaths: Vec<Death>,
+    #[serde(default)]
+    flows: EnergyFlows,
+    //pairs of animals that touched this step and are both looking to mate
+    #[serde(skip)]
+    mates: Vec<(usize,usize)>,
+    rng: SimRng,
 }
 
 impl Animals{
-    pub fn genesis()->Self{
+    pub fn genesis(seed: u64)->Self{
        Self{
            animals: vec![],
            next_free_id: 0,
+           innovations: Innovations::default(),
+           lineage: Lineage::default(),
+           deaths: vec![],
+           flows: EnergyFlows::default(),
+           mates: vec![],
+           rng: stream(seed, Stream::Animals),
        }
     }
     pub fn spawn(&mut self,sim_params: &SimParams){
-        let mut rng = rand::thread_rng();
-
-        let senses = SensoryInput{
-            animal_vision: rng.gen_range(0.0..12.0),
-            plant_vision: rng.gen_range(0.0..12.0),
-            fruit_vision: rng.gen_range(0.0..12.0),
-            rock_vision: rng.gen_range(0.0..12.0),
-        };
+        let rng = &mut self.rng;
 
-        let mut brain = Brain{ network: Network::zero(&[11,11,4])};
-        brain.network.mutate(0.5,0.4);
+        let mut brain = Brain{ network: Network::zero(&[11 + MEMORY_CELLS,11,4 + MEMORY_CELLS], &mut self.innovations), memory: vec![0.0; MEMORY_CELLS]};
+        brain.network.mutate(0.5,0.4,rng);
 
-        let max_stats = MaxStats{ speed: rng.gen_range(1.0..4.0), size: rng.gen_range(0.16..0.5), attack: rng.gen_range(0.0..10.)};
-        let mut body = Instance::new([rng.gen_range(CELL_SIZE*2.0..sim_params.world.width -CELL_SIZE*2.0), rng.gen_range(CELL_SIZE*2.0..sim_params.world.height -CELL_SIZE*2.0)],[0.0,0.0,0.0], rng.gen_range(-PI..PI),max_stats.size * 0.5);
-        let hue = rng.gen_range(0.0..=1.0);
-        body.set_hsl(hue,1.0);
+        let genome = Genome::random(rng);
+        let body = Instance::new([rng.random_range(CELL_SIZE*2.0..sim_params.world.width -CELL_SIZE*2.0), rng.random_range(CELL_SIZE*2.0..sim_params.world.height -CELL_SIZE*2.0)],[0.0,0.0,0.0], rng.random_range(-PI..PI),genome[Gene::Size] * 0.5);
         let resources = Resources{ energy: 2000.0, protein: 0.0, max_energy: body.scale * 20000., max_protein: body.scale * 400. };
-        let reproduction_stats = ReproductionStats{ offspring_investment: rng.gen_range(0.0..10.0), birth_timer: 0.0};
-        let combat_stats = CombatStats{ carnivore_factor: rng.gen_range(0.0..=1.0), aggression: 0.0, attack: max_stats.attack * 0.5, speed: max_stats.speed * 0.5, };
-        let ideal_temp = rng.gen_range(0.0..=35.0);
-        let temp_tolerance = rng.gen_range(1.0..=15.0);
 
-        let animal = Animal{
+        let mut animal = Animal{
             id: self.next_free_id,
             generation: 0,
+            parent_id: None,
+            mate_id: None,
             species_id: 0,
             maturity: 0.0,
-            lean_mass: body.scale * 30. + combat_stats.speed * 8.0 + combat_stats.attack * 5.0,
-            hue,
+            lean_mass: 0.0,
+            hue: 0.0,
             resources,
             body,
             brain,
-            senses,
-            max_stats,
-            reproduction_stats,
-            combat_stats,
+            senses: SensoryInput::default(),
+            genome,
+            reproduction_stats: ReproductionStats::default(),
+            combat_stats: CombatStats::default(),
             age: 0.0,
-            temperature: ideal_temp,
-            temp_tolerance,
-            ideal_temp,
+            temperature: 0.0,
+            temp_tolerance: 0.0,
+            ideal_temp: 0.0,
+            offspring_count: 0,
+            death_cause: None,
         };
+        animal.express();
+        animal.temperature = animal.ideal_temp;
 
+        self.lineage.record(animal.id, [None, None]);
         self.next_free_id += 1;
 
+        self.flows.add(FlowKind::Spawned, Flow::new(animal.resources.energy, animal.resources.protein));
+
         self.animals.push(animal);
     }
     pub fn kill(&mut self){
         (0..self.count()).rev().for_each(|i|{
             if self.animals.index(i).resources.energy <= 0.{
+                let animal = self.animals.index(i);
+                self.deaths.push(Death{
+                    id: animal.id,
+                    cause: animal.death_cause.unwrap_or(DeathCause::Starvation),
+                    age: animal.age,
+                    offspring: animal.offspring_count,
+                });
+                self.flows.add(FlowKind::Removed, Flow::new(animal.resources.energy, animal.resources.protein));
                 self.remove(i);
             }
         });
@@ -206,25 +309,51 @@ impl Animals{
 
             animal.brain.network.input(input);
 
-            if animal.maturity == 10. && animal.reproduction_stats.birth_timer <= 0. && animal.resources.energy > ((animal.reproduction_stats.offspring_investment/10.)+0.5)*animal.lean_mass*5.0 * sim_params.animals.reproduction_energy_cost + animal.resources.max_energy * 0.3 && animal.resources.protein > animal.lean_mass*0.2*((animal.reproduction_stats.offspring_investment/10.)+0.5) * sim_params.animals.reproduction_protein_cost{
-                animal.reproduction_stats.birth_timer = (10. + animal.reproduction_stats.offspring_investment * 4.) * sim_params.animals.reproduction_time;
-                animal.resources.energy -= ((animal.reproduction_stats.offspring_investment/10.)+0.5)*animal.lean_mass*5.0 * sim_params.animals.reproduction_energy_cost;
-                animal.resources.protein -= animal.lean_mass*0.4*((animal.reproduction_stats.offspring_investment/10.)+0.5) * sim_params.animals.reproduction_protein_cost;
+            if !sim_params.animals.sexual_reproduction && animal.can_reproduce(sim_params){
+                self.flows.add(FlowKind::Reproduction, animal.pay_reproduction_cost(sim_params));
+                animal.offspring_count += 1;
 
-                let mut offspring = animal.offspring(sim_params,species_list);
+                let mut offspring = animal.offspring(None,sim_params,species_list,&mut self.innovations,&mut self.rng);
                 offspring.id = self.next_free_id;
+                self.flows.add(FlowKind::Eggs, Flow::new(offspring.resources.energy, offspring.resources.protein));
 
+                self.lineage.record(offspring.id, [offspring.parent_id, offspring.mate_id]);
                 self.next_free_id += 1;
 
                 eggs.spawn(animal.body.position,offspring);
             }
         }
 
+        for (i, j) in std::mem::take(&mut self.mates){
+            if !sim_params.animals.sexual_reproduction || !self.animals[i].can_reproduce(sim_params) || !self.animals[j].can_reproduce(sim_params){
+                continue;
+            }
+            self.flows.add(FlowKind::Reproduction, self.animals[i].pay_reproduction_cost(sim_params));
+            self.flows.add(FlowKind::Reproduction, self.animals[j].pay_reproduction_cost(sim_params));
+            self.animals[i].offspring_count += 1;
+            self.animals[j].offspring_count += 1;
+
+            let mut offspring = self.animals[i].offspring(Some(&self.animals[j]),sim_params,species_list,&mut self.innovations,&mut self.rng);
+            offspring.id = self.next_free_id;
+            self.flows.add(FlowKind::Eggs, Flow::new(offspring.resources.energy, offspring.resources.protein));
+
+            self.lineage.record(offspring.id, [offspring.parent_id, offspring.mate_id]);
+            self.next_free_id += 1;
+
+            eggs.spawn(self.animals[i].body.position,offspring);
+        }
+
         let arc_rocks = Arc::new(rock_map);
         let arc_temperature = Arc::new(temp_map);
 
-        self.animals.par_iter_mut().for_each(|animal|{
+        //each animal reports what it burned so the total can be summed in a fixed order
+        let metabolism: Vec<Flow> = self.animals.par_iter_mut().map(|animal|{
+            let (energy, protein) = (animal.resources.energy, animal.resources.protein);
+
             let response = animal.brain.network.propagate();
+            //only moving forward and aggression need to be positive, so that walking backwards or negative attacks aren't possible
+            //turning keeps its sign so a negative turn left turns right instead
+            let forward = response[0].max(0.);
 
             /*let start = animal.body.position[0];
             animal.body.position[0] += response.index(0).max(0.0) * 0.01 * animal.body.rotation.cos() * animal.combat_stats.speed;
@@ -255,7 +384,7 @@ impl Animals{
             }*/
 
             let start = animal.body.position[0];
-            animal.body.position[0] += response.index(0).min(1.0) * 0.008 * animal.body.rotation.cos() * animal.combat_stats.speed * sim_params.animals.movement_speed;
+            animal.body.position[0] += forward.min(1.0) * 0.008 * animal.body.rotation.cos() * animal.combat_stats.speed * sim_params.animals.movement_speed;
 
             let i = (animal.body.position[0] * DIV) as usize * collisions.cells_height + (animal.body.position[1] * DIV) as usize;
             if arc_rocks.rocks[i] > 0{
@@ -263,7 +392,7 @@ impl Animals{
             }
 
             let start = animal.body.position[1];
-            animal.body.position[1] += response.index(0).min(1.0) * 0.008 * animal.body.rotation.sin() * animal.combat_stats.speed * sim_params.animals.movement_speed;
+            animal.body.position[1] += forward.min(1.0) * 0.008 * animal.body.rotation.sin() * animal.combat_stats.speed * sim_params.animals.movement_speed;
 
             let i = (animal.body.position[0] * DIV) as usize * collisions.cells_height + (animal.body.position[1] * DIV) as usize;
             if arc_rocks.rocks[i] > 0{
@@ -274,19 +403,21 @@ impl Animals{
             let temp_diff = temperature - animal.temperature;
             animal.temperature += (temp_diff/sim_params.animals.temperature_sensitivity)/60.;
 
-            if (animal.temperature - animal.ideal_temp).abs() > animal.temp_tolerance{
+            let temperature_stress = (animal.temperature - animal.ideal_temp).abs() > animal.temp_tolerance;
+            if temperature_stress{
                 animal.resources.energy -= 1.0;
             }
 
-            animal.body.rotation += response.index(1).min(1.0) * 0.04 * animal.combat_stats.speed * sim_params.animals.turning_speed;
-            animal.body.rotation -= response.index(2).min(1.0) * 0.04 * animal.combat_stats.speed * sim_params.animals.turning_speed;
-            animal.combat_stats.aggression = response.index(3).min(1.0);
+            animal.body.rotation += response.index(1).clamp(-1., 1.) * 0.04 * animal.combat_stats.speed * sim_params.animals.turning_speed;
+            animal.body.rotation -= response.index(2).clamp(-1., 1.) * 0.04 * animal.combat_stats.speed * sim_params.animals.turning_speed;
+            animal.combat_stats.aggression = response.index(3).clamp(0., 1.);
+            animal.brain.memory = response[4..].iter().map(|cell| cell.clamp(-1., 1.)).collect();
            // animal.reproduction_stats.birth_desire = response.index(4).min(1.0);
 
             animal.resources.energy -= //1 energy per sec at min ish
                 animal.body.scale * 0.042 * sim_params.animals.size_energy_cost + // 0.08 -> 0.5
-                response.index(0) * animal.combat_stats.speed * 0.0067 * sim_params.animals.speed_energy_cost + // 0.5 -> 4
-                (response.index(1)+response.index(2)) * animal.combat_stats.speed * 0.0067 * sim_params.animals.turning_energy_cost + // 0.5 -> 4
+                forward * animal.combat_stats.speed * 0.0067 * sim_params.animals.speed_energy_cost + // 0.5 -> 4
+                (response.index(1).abs() + response.index(2).abs()) * animal.combat_stats.speed * 0.0067 * sim_params.animals.turning_energy_cost + // 0.5 -> 4
                 0.001 * animal.combat_stats.aggression * animal.combat_stats.attack * sim_params.animals.attack_energy_cost + // 0 -> 10
                 (animal.senses.animal_vision + animal.senses.rock_vision + animal.senses.plant_vision + animal.senses.fruit_vision) * 0.0001 * sim_params.animals.vision_energy_cost; // 0 -> 48
 
@@ -295,9 +426,9 @@ impl Animals{
             if animal.maturity < 10. && animal.resources.protein > animal.lean_mass*0.3  {
                 animal.maturity += 1.;
                 animal.resources.protein -= animal.lean_mass*0.3;
-                animal.combat_stats.attack = animal.max_stats.attack * (0.5 + animal.maturity * 0.05);
-                animal.combat_stats.speed = animal.max_stats.speed * (0.5 + animal.maturity * 0.05);
-                animal.body.scale = animal.max_stats.size * (0.5 + animal.maturity * 0.05);
+                animal.combat_stats.attack = animal.genome[Gene::Attack] * (0.5 + animal.maturity * 0.05);
+                animal.combat_stats.speed = animal.genome[Gene::Speed] * (0.5 + animal.maturity * 0.05);
+                animal.body.scale = animal.genome[Gene::Size] * (0.5 + animal.maturity * 0.05);
                 animal.lean_mass = sim_params.animals.attack_protein_cost * animal.combat_stats.attack * 2.0 + sim_params.animals.speed_protein_cost * animal.combat_stats.speed * 4.0 + sim_params.animals.size_protein_cost * animal.body.scale * 32.;
                 animal.resources.max_protein = animal.body.scale * 400.;
                 animal.resources.max_energy = animal.body.scale *10000.;
@@ -326,8 +457,18 @@ impl Animals{
             animal.age+=1./60.;
             if animal.age > 36000. * sim_params.animals.lifespan{
                 animal.resources.energy = 0.;
+                animal.death_cause.get_or_insert(DeathCause::OldAge);
             }
-        });
+            //an animal that runs out of energy while too hot or cold is counted as killed by the temperature
+            else if animal.resources.energy <= 0. && animal.death_cause.is_none(){
+                animal.death_cause = Some(if temperature_stress { DeathCause::Temperature } else { DeathCause::Starvation });
+            }
+
+            Flow::new(energy - animal.resources.energy, protein - animal.resources.protein)
+        }).collect();
+
+        metabolism.into_iter().for_each(|flow| self.flows.add(FlowKind::Metabolism, flow));
+        self.flows.steps += 1;
     }
 
     pub fn handle_animal_collision(&mut self, animal_id: usize, other_animal_id: usize,efficiency: f32){
@@ -343,6 +484,13 @@ impl Animals{
         }
     }
 
+    pub fn handle_mating(&mut self, animal_id: usize, other_animal_id: usize, sim_params: &SimParams){
+        let (animal, other) = (&self.animals[animal_id], &self.animals[other_animal_id]);
+        if animal.species_id == other.species_id && animal.can_reproduce(sim_params) && other.can_reproduce(sim_params){
+            self.mates.push((animal_id, other_animal_id));
+        }
+    }
+
     fn animal_collision(&mut self,animal_id: usize,other_animal_id: usize,efficiency: f32,){
         let efficiency = (1.0-0.7*(self.animals[animal_id].combat_stats.carnivore_factor-1.).powf(2.)) * efficiency;
 
@@ -350,22 +498,37 @@ impl Animals{
             ((self.animals.index(other_animal_id).resources.energy + self.animals.index(other_animal_id).lean_mass * 5.) * efficiency,
             (self.animals.index(other_animal_id).resources.protein + self.animals.index(other_animal_id).lean_mass ) * efficiency);
 
-        self.animals.index_mut(other_animal_id).resources.energy = 0.;
+        let prey = self.animals.index_mut(other_animal_id);
+        self.flows.add(FlowKind::Prey, Flow::new(prey.resources.energy, 0.));
+        let prey_species = prey.species_id;
+
+        prey.resources.energy = 0.;
+        prey.death_cause = Some(DeathCause::Predation);
 
         let animal = self.animals.index_mut(animal_id);
 
-        animal.resources.add((energy,protein));
+        let absorbed = animal.resources.add((energy,protein));
+        self.flows.add(FlowKind::Predation, absorbed);
+        self.flows.feed(animal.species_id, Food::Species(prey_species), absorbed.energy as f32);
     }
 
     pub fn handle_plant_collision(&mut self, animal_id: usize, resources: (f32,f32),efficiency: f32){
         let efficiency = (1.2+0.8*(self.animals[animal_id].combat_stats.carnivore_factor-1.).powf(2.)) * efficiency;
 
-        self.animals[animal_id].resources.add((resources.0 * efficiency,resources.1 * efficiency));
+        let absorbed = self.animals[animal_id].resources.add((resources.0 * efficiency,resources.1 * efficiency));
+
+        self.flows.add(FlowKind::Plants, Flow::new(resources.0, resources.1));
+        self.flows.add(FlowKind::Herbivory, absorbed);
+        self.flows.feed(self.animals[animal_id].species_id, Food::Plants, absorbed.energy as f32);
     }
 
     pub fn handle_fruit_collision(&mut self, animal_id: usize, resources: (f32,f32),efficiency:f32){
         let efficiency = (1.0-1.8*(self.animals[animal_id].combat_stats.carnivore_factor-0.4).powf(2.)) * efficiency;
-        self.animals[animal_id].resources.add((resources.0 * efficiency,resources.1 * efficiency));
+        let absorbed = self.animals[animal_id].resources.add((resources.0 * efficiency,resources.1 * efficiency));
+
+        self.flows.add(FlowKind::Fruit, Flow::new(resources.0, resources.1));
+        self.flows.add(FlowKind::Herbivory, absorbed);
+        self.flows.feed(self.animals[animal_id].species_id, Food::Fruit, absorbed.energy as f32);
     }
 
     pub fn remove(&mut self, i: usize){
@@ -381,8 +544,26 @@ impl Animals{
     pub fn count(&self)->usize{
         self.animals.len()
     }
+    pub fn lineage(&self) -> &Lineage{
+        &self.lineage
+    }
+    pub fn take_flows(&mut self) -> EnergyFlows{
+        std::mem::take(&mut self.flows)
+    }
+    pub fn take_deaths(&mut self) -> Vec<Death>{
+        std::mem::take(&mut self.deaths)
+    }
+    //deaths since the stats last took them
+    pub fn deaths(&self) -> &[Death]{
+        &self.deaths
+    }
+    //the id the next animal to be born will be given
+    pub fn next_id(&self) -> usize{
+        self.next_free_id
+    }
 
     pub fn birth(&mut self, animal: Animal){
+        self.flows.add(FlowKind::Hatched, Flow::new(animal.resources.energy, animal.resources.protein));
         self.animals.push(animal);
     }
 }
\ No newline at end of file
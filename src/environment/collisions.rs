@@ -20,6 +20,13 @@ pub struct Collisions{
     pub cells_height: usize,
     pub cells_width: usize,
 }
+//the cell each object was in when its grid was last built, which is all it takes to build the grid again exactly as it was
+#[derive(Default,Clone,Serialize,Deserialize)]
+pub struct GridCells{
+    pub animals: Vec<usize>,
+    pub plants: Vec<usize>,
+    pub fruit: Vec<usize>,
+}
 #[derive(Default,Clone,Serialize,Deserialize)]
 pub struct Cell{
     pub object_ids: Vec<usize>,
@@ -74,6 +81,18 @@ impl Collisions{
             self.fruit_grid[i].add(id);
         });
     }
+    pub fn cells(&self) -> GridCells{
+        GridCells{
+            animals: grid_cells(&self.animals_grid),
+            plants: grid_cells(&self.plants_grid),
+            fruit: grid_cells(&self.fruit_grid),
+        }
+    }
+    pub fn restore(&mut self, cells: &GridCells){
+        restore_grid(&mut self.animals_grid, &cells.animals);
+        restore_grid(&mut self.plants_grid, &cells.plants);
+        restore_grid(&mut self.fruit_grid, &cells.fruit);
+    }
     pub fn handle_collisions(&mut self, animals: &mut Animals, plants: &mut Plants,fruit: &mut Fruits,sim_params: &SimParams){
         for x in 0..self.cells_width{
             for y in 0..self.cells_height{
@@ -97,6 +116,9 @@ impl Collisions{
 
                                 if distance < (animals.animals[other_animal_id].body.scale + animal_body.scale) * 0.5{
                                     animals.handle_animal_collision(animal_id,other_animal_id,sim_params.animals.carnivory_efficiency);
+                                    if sim_params.animals.sexual_reproduction {
+                                        animals.handle_mating(animal_id,other_animal_id,sim_params);
+                                    }
                                 }
                             }
                             //plant
@@ -138,4 +160,15 @@ impl Collisions{
             }
         }
     }
+}
+
+fn grid_cells(grid: &[Cell]) -> Vec<usize>{
+    let mut cells = vec![0; grid.iter().map(|cell| cell.count()).sum()];
+    grid.iter().enumerate().for_each(|(i, cell)| cell.object_ids.iter().for_each(|id| cells[*id] = i));
+    cells
+}
+//objects are added in id order, as they are when a grid is built
+fn restore_grid(grid: &mut [Cell], cells: &[usize]){
+    grid.iter_mut().for_each(|cell| cell.clear());
+    cells.iter().enumerate().for_each(|(id, i)| grid[*i].add(id));
 }
\ No newline at end of file
@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Default,Clone,Copy,Serialize,Deserialize)]
+pub struct Flow{
+    pub energy: f64,
+    pub protein: f64,
+}
+impl Flow{
+    pub fn new(energy: f32, protein: f32) -> Self{
+        Self{ energy: energy as f64, protein: protein as f64 }
+    }
+    fn add(&mut self, flow: Flow){
+        self.energy += flow.energy;
+        self.protein += flow.protein;
+    }
+}
+
+//every way resources move into, out of or around the animal population
+//the energy held by living animals changes by exactly herbivory + predation + hatched + spawned - prey - metabolism - reproduction - removed
+#[derive(Clone,Copy,PartialEq,Eq,Debug)]
+pub enum FlowKind{
+    //offered by plants and fruit that were eaten, before digestive efficiency
+    Plants,
+    Fruit,
+    //absorbed by animals from plants and fruit
+    Herbivory,
+    //absorbed by predators from their prey
+    Predation,
+    //taken out of the reserves of animals that were eaten
+    Prey,
+    //burned by animals, protein being spent on growing up
+    Metabolism,
+    //paid by parents to lay an egg
+    Reproduction,
+    //given to the offspring inside each egg
+    Eggs,
+    //brought into the world by eggs hatching
+    Hatched,
+    //brought into the world by animals spawned to keep the population up
+    Spawned,
+    //left in bodies when they were removed from the world
+    Removed,
+}
+impl FlowKind{
+    pub const ALL: [FlowKind;11] = [FlowKind::Plants, FlowKind::Fruit, FlowKind::Herbivory, FlowKind::Predation, FlowKind::Prey, FlowKind::Metabolism, FlowKind::Reproduction, FlowKind::Eggs, FlowKind::Hatched, FlowKind::Spawned, FlowKind::Removed];
+
+    pub fn name(&self) -> &'static str{
+        match self {
+            FlowKind::Plants => "Plants eaten",
+            FlowKind::Fruit => "Fruit eaten",
+            FlowKind::Herbivory => "Herbivory",
+            FlowKind::Predation => "Predation",
+            FlowKind::Prey => "Prey",
+            FlowKind::Metabolism => "Metabolism",
+            FlowKind::Reproduction => "Reproduction",
+            FlowKind::Eggs => "Egg investment",
+            FlowKind::Hatched => "Hatched",
+            FlowKind::Spawned => "Spawned",
+            FlowKind::Removed => "Removed",
+        }
+    }
+}
+
+#[derive(Clone,Copy,PartialEq,Eq,PartialOrd,Ord,Debug,Serialize,Deserialize)]
+pub enum Food{
+    Plants,
+    Fruit,
+    Species(usize),
+}
+
+//energy absorbed by one species from one food source
+#[derive(Clone,Serialize,Deserialize)]
+pub struct FeedingLink{
+    pub eater: usize,
+    pub food: Food,
+    pub energy: f64,
+}
+
+//flows summed since they were last collected by the stats
+#[derive(Default,Clone,Serialize,Deserialize)]
+pub struct EnergyFlows{
+    pub steps: usize,
+    flows: Vec<Flow>,
+    pub links: Vec<FeedingLink>,
+}
+impl EnergyFlows{
+    pub fn add(&mut self, kind: FlowKind, flow: Flow){
+        self.flows.resize(FlowKind::ALL.len(), Flow::default());
+        self.flows[kind as usize].add(flow);
+    }
+    pub fn get(&self, kind: FlowKind) -> Flow{
+        self.flows.get(kind as usize).copied().unwrap_or_default()
+    }
+    pub fn feed(&mut self, eater: usize, food: Food, energy: f32){
+        add_link(&mut self.links, eater, food, energy as f64);
+    }
+    pub fn merge(&mut self, other: EnergyFlows){
+        self.steps += other.steps;
+        FlowKind::ALL.iter().for_each(|kind| self.add(*kind, other.get(*kind)));
+        other.links.into_iter().for_each(|link| add_link(&mut self.links, link.eater, link.food, link.energy));
+    }
+    //the change in the energy held by living animals that the flows account for
+    pub fn net_energy(&self) -> f64{
+        let flow = |kind: FlowKind| self.get(kind).energy;
+        flow(FlowKind::Herbivory) + flow(FlowKind::Predation) + flow(FlowKind::Hatched) + flow(FlowKind::Spawned)
+            - flow(FlowKind::Prey) - flow(FlowKind::Metabolism) - flow(FlowKind::Reproduction) - flow(FlowKind::Removed)
+    }
+}
+
+pub fn add_link(links: &mut Vec<FeedingLink>, eater: usize, food: Food, energy: f64){
+    match links.iter_mut().find(|link| link.eater == eater && link.food == food) {
+        Some(link) => link.energy += energy,
+        None => links.push(FeedingLink{ eater, food, energy }),
+    }
+}
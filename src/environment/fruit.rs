@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use crate::environment::collisions::{CELL_SIZE, Collisions, DIV};
 use crate::environment::rocks::RockMap;
 use crate::rendering::instance::Instance;
+use crate::utilities::random::{SimRng, Stream, stream};
 use crate::utilities::simulation_parameters::SimParams;
 
 #[derive(Clone,Serialize,Deserialize)]
@@ -15,6 +16,7 @@ pub struct Fruit{
 pub struct Fruits{
     pub fruit: Vec<Fruit>,
     pub bodies: Vec<Instance>,
+    rng: SimRng,
 }
 #[derive(Clone,Serialize,Deserialize)]
 pub struct FruitSpawners{
@@ -26,11 +28,11 @@ impl FruitSpawners {
             fruit.spawn_near(rock_map,collisions,sim_params,spawner.position[0],spawner.position[1]);
         })
     }
-    pub fn random(&mut self,sim_params: &SimParams){
+    pub fn random(&mut self,sim_params: &SimParams,rng: &mut SimRng){
         if sim_params.world.fruit_spawners == 0 {return;}
         for _ in 0..sim_params.world.fruit_spawners{
-            let x = rand::thread_rng().gen_range(0..(sim_params.world.width*DIV - CELL_SIZE) as u32);
-            let y = rand::thread_rng().gen_range(0..(sim_params.world.height*DIV - CELL_SIZE) as u32);
+            let x = rng.random_range(0..(sim_params.world.width*DIV - CELL_SIZE) as u32);
+            let y = rng.random_range(0..(sim_params.world.height*DIV - CELL_SIZE) as u32);
 
             self.bodies.push(Instance::new([x as f32 * CELL_SIZE+CELL_SIZE*0.5,y as f32 * CELL_SIZE+CELL_SIZE*0.5],[0.3, 1.0, 0.0],PI/4.,CELL_SIZE*1.1));
         }
@@ -59,10 +61,11 @@ impl FruitSpawners {
     }
 }
 impl Fruits {
-    pub fn genesis()->Self{
+    pub fn genesis(seed: u64)->Self{
         Self{
             fruit: vec![],
             bodies: vec![],
+            rng: stream(seed, Stream::Fruit),
         }
     }
     pub fn remove(&mut self, i: usize){
@@ -92,8 +95,8 @@ impl Fruits {
 
     pub fn spawn_random(&mut self,rock_map: &RockMap, collisions: &Collisions, sim_params: &SimParams){
         for _trials in 0..100{
-            let x = rand::thread_rng().gen_range(0.0..sim_params.world.width);
-            let y = rand::thread_rng().gen_range(0.0..sim_params.world.height);
+            let x = self.rng.random_range(0.0..sim_params.world.width);
+            let y = self.rng.random_range(0.0..sim_params.world.height);
 
             let mut spawn = true;
 
@@ -116,14 +119,13 @@ impl Fruits {
     }
 
     pub fn spawn_near(&mut self,rock_map: &RockMap, collisions: &Collisions, sim_params: &SimParams,sx: f32, sy: f32){
-        let mut rng = rand::thread_rng();
         for _trials in 0..10{
-            let x = (sx + rand::thread_rng().gen_range(-sim_params.fruit.spawn_radius..=sim_params.fruit.spawn_radius)).clamp(0.,sim_params.world.width);
-            let y = (sy + rand::thread_rng().gen_range(-sim_params.fruit.spawn_radius..=sim_params.fruit.spawn_radius)).clamp(0.,sim_params.world.height);
+            let x = (sx + self.rng.random_range(-sim_params.fruit.spawn_radius..=sim_params.fruit.spawn_radius)).clamp(0.,sim_params.world.width);
+            let y = (sy + self.rng.random_range(-sim_params.fruit.spawn_radius..=sim_params.fruit.spawn_radius)).clamp(0.,sim_params.world.height);
 
             let mut spawn = true;
 
-            if rng.gen_bool((((x-sx).powf(2.) + (y-sy).powf(2.))/(sim_params.fruit.spawn_radius*sim_params.fruit.spawn_radius)).min(1.0) as f64){
+            if self.rng.random_bool((((x-sx).powf(2.) + (y-sy).powf(2.))/(sim_params.fruit.spawn_radius*sim_params.fruit.spawn_radius)).min(1.0) as f64){
                 spawn = false;
             }
             else {
@@ -0,0 +1,137 @@
+use std::ops::Index;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::utilities::random::SimRng;
+
+pub struct GeneDefinition{
+    pub name: &'static str,
+    pub min: f32,
+    pub max: f32,
+    pub mutation_scale: f32,
+    //wraps around the range instead of being clamped, e.g. hue
+    pub cyclic: bool,
+}
+
+#[derive(Clone,Copy,PartialEq)]
+pub enum Gene{
+    Speed,
+    Size,
+    Attack,
+    OffspringInvestment,
+    CarnivoreFactor,
+    AnimalVision,
+    PlantVision,
+    FruitVision,
+    RockVision,
+    TempTolerance,
+    IdealTemp,
+    Hue,
+}
+
+//indexed by Gene, adding a trait only needs a variant here, a definition here and a use in Animal::express
+pub const GENES: [GeneDefinition;12] = [
+    GeneDefinition{ name: "Speed", min: 1.0, max: 4.0, mutation_scale: 3.0, cyclic: false },
+    GeneDefinition{ name: "Size", min: 0.16, max: 0.5, mutation_scale: 0.4, cyclic: false },
+    GeneDefinition{ name: "Attack", min: 0.0, max: 10.0, mutation_scale: 10.0, cyclic: false },
+    GeneDefinition{ name: "Offspring investment", min: 0.0, max: 10.0, mutation_scale: 10.0, cyclic: false },
+    GeneDefinition{ name: "Carnivore factor", min: 0.0, max: 1.0, mutation_scale: 1.0, cyclic: false },
+    GeneDefinition{ name: "Animal vision", min: 0.0, max: 12.0, mutation_scale: 12.0, cyclic: false },
+    GeneDefinition{ name: "Plant vision", min: 0.0, max: 12.0, mutation_scale: 12.0, cyclic: false },
+    GeneDefinition{ name: "Fruit vision", min: 0.0, max: 12.0, mutation_scale: 12.0, cyclic: false },
+    GeneDefinition{ name: "Rock vision", min: 0.0, max: 12.0, mutation_scale: 12.0, cyclic: false },
+    GeneDefinition{ name: "Temp tolerance", min: 1.0, max: 15.0, mutation_scale: 14.0, cyclic: false },
+    GeneDefinition{ name: "Ideal temp", min: 0.0, max: 35.0, mutation_scale: 35.0, cyclic: false },
+    GeneDefinition{ name: "Hue", min: 0.0, max: 1.0, mutation_scale: 1.0, cyclic: true },
+];
+
+impl Gene{
+    pub fn definition(&self) -> &'static GeneDefinition{
+        &GENES[*self as usize]
+    }
+}
+
+#[derive(Clone,Serialize,Deserialize)]
+pub struct Genome{
+    genes: Vec<f32>,
+}
+
+impl Genome{
+    pub fn random(rng: &mut SimRng) -> Self{
+        Self{ genes: GENES.iter().map(|gene| rng.random_range(gene.min..=gene.max)).collect() }
+    }
+    pub fn mutate(&mut self, strength: f32, rate: f64, rng: &mut SimRng){
+        self.genes.iter_mut().zip(GENES.iter()).for_each(|(value, gene)|{
+            if rng.random_bool(rate) {
+                *value += gene.mutation_scale * rng.random_range(-strength..=strength);
+                *value = if gene.cyclic {
+                    gene.min + (*value - gene.min).rem_euclid(gene.max - gene.min)
+                } else {
+                    value.clamp(gene.min, gene.max)
+                };
+            }
+        });
+    }
+    pub fn crossover(&self, other: &Genome, rng: &mut SimRng) -> Self{
+        Self{ genes: self.genes.iter().zip(other.genes.iter()).map(|(a, b)| if rng.random_bool(0.5) { *b } else { *a }).collect() }
+    }
+    //mean difference across genes, each scaled by its range so that every gene counts equally
+    pub fn compare(&self, other: &Genome) -> f32{
+        self.genes.iter().zip(other.genes.iter()).zip(GENES.iter())
+            .map(|((a, b), gene)| (a - b).abs() / (gene.max - gene.min))
+            .sum::<f32>() / GENES.len() as f32
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&'static GeneDefinition, f32)> + '_{
+        GENES.iter().zip(self.genes.iter().copied())
+    }
+}
+
+impl Index<Gene> for Genome{
+    type Output = f32;
+
+    fn index(&self, gene: Gene) -> &f32{
+        &self.genes[gene as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::utilities::random::{stream, Stream};
+
+    #[test]
+    fn crossover_takes_each_gene_from_a_parent(){
+        let mut rng = stream(7, Stream::Animals);
+        let (a, b) = (Genome::random(&mut rng), Genome::random(&mut rng));
+
+        for _ in 0..100 {
+            let child = a.crossover(&b, &mut rng);
+            assert_eq!(child.genes.len(), GENES.len());
+            for ((gene, a), b) in child.genes.iter().zip(&a.genes).zip(&b.genes) {
+                assert!(gene == a || gene == b);
+            }
+        }
+    }
+
+    #[test]
+    fn mutation_stays_within_bounds(){
+        let mut rng = stream(7, Stream::Animals);
+        let mut genome = Genome::random(&mut rng);
+
+        for _ in 0..1000 {
+            genome.mutate(1.0, 1.0, &mut rng);
+            for (gene, value) in genome.iter() {
+                assert!(value >= gene.min && value <= gene.max, "{} out of bounds at {}", gene.name, value);
+            }
+        }
+    }
+
+    #[test]
+    fn compare_is_symmetric(){
+        let mut rng = stream(7, Stream::Animals);
+        let (a, b) = (Genome::random(&mut rng), Genome::random(&mut rng));
+
+        assert_eq!(a.compare(&a), 0.);
+        assert_eq!(a.compare(&b), b.compare(&a));
+        assert!(a.compare(&b) > 0.);
+    }
+}
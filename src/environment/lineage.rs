@@ -0,0 +1,112 @@
+use std::collections::{HashMap, VecDeque};
+use std::collections::hash_map::Entry;
+use serde::{Deserialize, Serialize};
+
+//parents of every animal that has ever lived, indexed by id, so ancestry survives after animals die
+#[derive(Default,Clone,Serialize,Deserialize)]
+pub struct Lineage{
+    //the parent that laid the egg
+    parents: Vec<Option<usize>>,
+    //the other parent when reproduction is sexual
+    #[serde(default)]
+    mates: Vec<Option<usize>>,
+}
+
+impl Lineage{
+    pub fn record(&mut self, id: usize, parents: [Option<usize>;2]){
+        if self.parents.len() <= id {
+            self.parents.resize(id + 1, None);
+        }
+        if self.mates.len() <= id {
+            self.mates.resize(id + 1, None);
+        }
+        self.parents[id] = parents[0];
+        self.mates[id] = parents[1];
+    }
+    pub fn parent(&self, id: usize) -> Option<usize>{
+        self.parents.get(id).copied().flatten()
+    }
+    pub fn parents(&self, id: usize) -> [Option<usize>;2]{
+        [self.parent(id), self.mates.get(id).copied().flatten()]
+    }
+    //the animal itself followed by its parent, grandparent and so on back to a founder
+    //only follows the parent that laid each egg, so with sexual reproduction it is the maternal line
+    pub fn line_of_descent(&self, id: usize) -> Vec<usize>{
+        let mut line = vec![id];
+        while let Some(parent) = self.parent(*line.last().unwrap()) {
+            line.push(parent);
+        }
+        line
+    }
+    //every ancestor of an animal through either parent, including itself, with the fewest generations back it is reached in
+    pub fn ancestors(&self, id: usize) -> HashMap<usize, usize>{
+        let mut ancestors = HashMap::from([(id, 0)]);
+        let mut queue = VecDeque::from([id]);
+        while let Some(next) = queue.pop_front() {
+            let generations = ancestors[&next] + 1;
+            for parent in self.parents(next).into_iter().flatten() {
+                if let Entry::Vacant(entry) = ancestors.entry(parent) {
+                    entry.insert(generations);
+                    queue.push_back(parent);
+                }
+            }
+        }
+        ancestors
+    }
+    //most recent common ancestor of two animals through either parent along with how many generations back it is from each,
+    //None if they descend from different founders
+    pub fn common_ancestor(&self, a: usize, b: usize) -> Option<(usize, usize, usize)>{
+        let ancestors_a = self.ancestors(a);
+        let ancestors_b = self.ancestors(b);
+
+        //ties go to the later born, as ids are handed out in order
+        ancestors_a.iter()
+            .filter_map(|(ancestor, back_a)| ancestors_b.get(ancestor).map(|back_b| (*ancestor, *back_a, *back_b)))
+            .min_by_key(|(ancestor, back_a, back_b)| (back_a + back_b, usize::MAX - ancestor))
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    //0 and 1 are founders, 2 and 3 are 0's offspring, 4 is 2 and 3's and 5 is 3 and 1's
+    fn family() -> Lineage{
+        let mut lineage = Lineage::default();
+        lineage.record(0, [None, None]);
+        lineage.record(1, [None, None]);
+        lineage.record(2, [Some(0), None]);
+        lineage.record(3, [Some(0), None]);
+        lineage.record(4, [Some(2), Some(3)]);
+        lineage.record(5, [Some(1), Some(3)]);
+        lineage
+    }
+
+    #[test]
+    fn line_of_descent_follows_the_egg_layer(){
+        assert_eq!(family().line_of_descent(4), vec![4, 2, 0]);
+        assert_eq!(family().line_of_descent(5), vec![5, 1]);
+    }
+
+    #[test]
+    fn common_ancestor_of_siblings_is_their_parent(){
+        assert_eq!(family().common_ancestor(2, 3), Some((0, 1, 1)));
+    }
+
+    #[test]
+    fn common_ancestor_of_an_animal_and_its_descendant_is_itself(){
+        assert_eq!(family().common_ancestor(0, 4), Some((0, 0, 2)));
+    }
+
+    #[test]
+    fn common_ancestor_follows_both_parents(){
+        //5's maternal line never meets 4's, but they share 3 as a parent
+        assert_eq!(family().common_ancestor(4, 5), Some((3, 1, 1)));
+    }
+
+    #[test]
+    fn founders_have_no_common_ancestor(){
+        assert_eq!(family().common_ancestor(0, 1), None);
+        assert_eq!(family().common_ancestor(2, 1), None);
+    }
+}
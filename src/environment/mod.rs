@@ -1,7 +1,10 @@
 pub mod animal;
 pub mod plants;
 pub mod neural_network;
+pub mod genome;
+pub mod lineage;
 pub mod eggs;
+pub mod energy;
 pub mod collisions;
 pub mod species;
 pub mod rocks;
@@ -1,5 +1,7 @@
+use std::collections::HashMap;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use crate::utilities::random::SimRng;
 
 #[derive(Clone,Serialize,Deserialize)]
 pub struct Network{
@@ -13,10 +15,102 @@ pub struct Layer{
 
 #[derive(Clone,Serialize,Deserialize)]
 pub struct Neuron{
+    pub id: usize,
     pub activation: f32,
     pub bias: f32,
-    pub weights: Vec<f32>,
+    #[serde(default)]
+    pub function: ActivationFunction,
+    pub connections: Vec<Connection>,
 }
+
+#[derive(Default,Clone,Copy,PartialEq,Serialize,Deserialize)]
+pub enum ActivationFunction{
+    #[default]
+    Relu,
+    Tanh,
+    Sigmoid,
+    Sine,
+    Identity,
+    Step,
+}
+
+impl ActivationFunction{
+    pub const ALL: [ActivationFunction;6] = [Self::Relu, Self::Tanh, Self::Sigmoid, Self::Sine, Self::Identity, Self::Step];
+
+    pub fn apply(&self, x: f32) -> f32{
+        match self {
+            Self::Relu => x.max(0.),
+            Self::Tanh => x.tanh(),
+            Self::Sigmoid => 1. / (1. + (-x).exp()),
+            Self::Sine => x.sin(),
+            Self::Identity => x,
+            Self::Step => if x > 0. { 1. } else { 0. },
+        }
+    }
+    pub fn name(&self) -> &'static str{
+        match self {
+            Self::Relu => "ReLU",
+            Self::Tanh => "Tanh",
+            Self::Sigmoid => "Sigmoid",
+            Self::Sine => "Sine",
+            Self::Identity => "Identity",
+            Self::Step => "Step",
+        }
+    }
+    pub fn symbol(&self) -> &'static str{
+        match self {
+            Self::Relu => "R",
+            Self::Tanh => "T",
+            Self::Sigmoid => "Sg",
+            Self::Sine => "Sn",
+            Self::Identity => "I",
+            Self::Step => "St",
+        }
+    }
+}
+
+#[derive(Clone,Serialize,Deserialize)]
+pub struct Connection{
+    //layer and neuron index of the source, which can be in any earlier layer
+    pub from: [usize;2],
+    pub weight: f32,
+    pub innovation: usize,
+}
+
+//hands out the same innovation number whenever the same structure evolves independently, so genomes can be lined up
+#[derive(Default,Clone,Serialize,Deserialize)]
+pub struct Innovations{
+    connections: HashMap<u64,usize>,
+    splits: HashMap<usize,usize>,
+    next_connection: usize,
+    next_node: usize,
+}
+
+impl Innovations{
+    fn connection(&mut self, from_id: usize, to_id: usize) -> usize{
+        let key = ((from_id as u64) << 32) | to_id as u64;
+        let next = &mut self.next_connection;
+        *self.connections.entry(key).or_insert_with(|| {
+            *next += 1;
+            *next - 1
+        })
+    }
+    fn split(&mut self, innovation: usize) -> usize{
+        let next = &mut self.next_node;
+        *self.splits.entry(innovation).or_insert_with(|| {
+            *next += 1;
+            *next - 1
+        })
+    }
+    fn node(&mut self) -> usize{
+        self.next_node += 1;
+        self.next_node - 1
+    }
+    fn reserve_nodes(&mut self, count: usize){
+        self.next_node = self.next_node.max(count);
+    }
+}
+
 impl Network{
     pub fn input(&mut self,inputs: Vec<f32>){
         self.layers[0].neurons.iter_mut().zip(inputs).for_each(|(neuron,input)|{
@@ -24,95 +118,367 @@ impl Network{
         });
     }
     pub fn propagate(&mut self) -> Vec<f32>{
-        let inputs = self.layers[0].activations();
+        for i in 1..self.layers.len(){
+            let (previous, layers) = self.layers.split_at_mut(i);
+            layers[0].propagate(previous);
+        }
 
-        self.layers.iter_mut().skip(1).fold(inputs,|inputs,layer|{
-            layer.propagate(inputs)
-        })
+        self.layers.last().unwrap().activations()
     }
-    pub fn random(layers: &[usize]) -> Self{
-        let mut layers = layers.to_vec();
-        layers.insert(0,0);
+    pub fn random(layers: &[usize], innovations: &mut Innovations, rng: &mut SimRng) -> Self{
+        let mut network = Self::zero(layers, innovations);
 
-        let layers = layers.windows(2).map(|layers| {
-            Layer::random(layers[0], layers[1])
-        }).collect();
+        network.layers.iter_mut().skip(1).for_each(|layer| layer.neurons.iter_mut().for_each(|neuron|{
+            neuron.bias = rng.random_range(-1.0..=1.0);
+            neuron.connections.iter_mut().for_each(|connection| connection.weight = rng.random_range(-1.0..=1.0));
+        }));
 
-        Self { layers }
+        network
     }
-    pub fn mutate(&mut self,strength: f32,probability: f32){
+    pub fn mutate(&mut self,strength: f32,probability: f32, rng: &mut SimRng){
         for i in 1..self.layers.len(){
-            self.layers[i].mutate(strength,probability);
+            self.layers[i].mutate(strength,probability,rng);
+        }
+    }
+    pub fn mutate_structure(&mut self, probability: f32, innovations: &mut Innovations, rng: &mut SimRng){
+        let probability = probability as f64;
+        if rng.random_bool(probability){ self.add_connection(innovations, rng); }
+        if rng.random_bool(probability){ self.remove_connection(rng); }
+        if rng.random_bool(probability){
+            let new_layer = rng.random_bool(0.5);
+            self.add_neuron(new_layer, innovations, rng);
         }
+        if rng.random_bool(probability){ self.remove_neuron(rng); }
     }
+    //compatibility distance: the share of connections the two networks don't have in common plus the mean weight and bias differences of those they do
     pub fn compare(&self, other: &Network) -> f32{
-        self.layers.iter().zip(other.layers.iter()).map(|layer| { layer.0.compare(layer.1) } ).sum::<f32>()/self.layers.len() as f32
+        let genes = self.connection_genes();
+        let other_genes = other.connection_genes();
+
+        let (mut i, mut j) = (0, 0);
+        let (mut matching, mut disjoint, mut weight_diff) = (0, 0, 0.);
+        while i < genes.len() && j < other_genes.len() {
+            if genes[i].0 == other_genes[j].0 {
+                weight_diff += (genes[i].1 - other_genes[j].1).abs();
+                matching += 1;
+                i += 1;
+                j += 1;
+            }
+            else if genes[i].0 < other_genes[j].0 {
+                disjoint += 1;
+                i += 1;
+            }
+            else {
+                disjoint += 1;
+                j += 1;
+            }
+        }
+        disjoint += genes.len() - i + other_genes.len() - j;
+
+        let (mut shared_neurons, mut bias_diff) = (0, 0.);
+        let biases: HashMap<usize,f32> = other.layers.iter().flat_map(|layer| layer.neurons.iter().map(|neuron| (neuron.id, neuron.bias))).collect();
+        self.layers.iter().flat_map(|layer| layer.neurons.iter()).for_each(|neuron|{
+            if let Some(bias) = biases.get(&neuron.id) {
+                bias_diff += (neuron.bias - bias).abs();
+                shared_neurons += 1;
+            }
+        });
+
+        let size = genes.len().max(other_genes.len()).max(1) as f32;
+        let weight_diff = if matching > 0 { weight_diff / matching as f32 } else { 0. };
+        let bias_diff = if shared_neurons > 0 { bias_diff / shared_neurons as f32 } else { 0. };
+
+        disjoint as f32 / size + weight_diff + bias_diff / 2.0
     }
-    pub fn zero(layers: &[usize])->Self{
-        let mut layers = layers.to_vec();
-        layers.insert(0,0);
-        let layers = layers.windows(2).map(|layers| { Layer::zero(layers[0], layers[1]) }).collect();
-        Self { layers }
+    //keeps this network's topology, taking each gene it shares with the other network from either parent at random
+    pub fn crossover(&self, other: &Network, rng: &mut SimRng) -> Network{
+        let mut child = self.clone();
+
+        let weights: HashMap<usize,f32> = other.layers.iter().flat_map(|layer| layer.neurons.iter().flat_map(|neuron| neuron.connections.iter().map(|connection| (connection.innovation, connection.weight)))).collect();
+        let neurons: HashMap<usize,&Neuron> = other.layers.iter().flat_map(|layer| layer.neurons.iter().map(|neuron| (neuron.id, neuron))).collect();
+
+        child.layers.iter_mut().flat_map(|layer| layer.neurons.iter_mut()).for_each(|neuron|{
+            if let Some(other_neuron) = neurons.get(&neuron.id) {
+                if rng.random_bool(0.5) {
+                    neuron.bias = other_neuron.bias;
+                    neuron.function = other_neuron.function;
+                }
+            }
+            neuron.connections.iter_mut().for_each(|connection|{
+                if let Some(weight) = weights.get(&connection.innovation) {
+                    if rng.random_bool(0.5) {
+                        connection.weight = *weight;
+                    }
+                }
+            });
+        });
+
+        child
+    }
+    pub fn zero(layers: &[usize], innovations: &mut Innovations)->Self{
+        //the starting layout is shared by every network so its node ids are just the neuron's position in it
+        let mut ids = 0;
+        let mut network = Self{ layers: layers.iter().map(|size|{
+            let neurons = (0..*size).map(|_|{
+                ids += 1;
+                Neuron::zero(ids - 1)
+            }).collect();
+            Layer { neurons }
+        }).collect() };
+        innovations.reserve_nodes(ids);
+
+        for i in 1..network.layers.len(){
+            for j in 0..network.layers[i].neurons.len(){
+                for k in 0..network.layers[i-1].neurons.len(){
+                    let innovation = innovations.connection(network.layers[i-1].neurons[k].id, network.layers[i].neurons[j].id);
+                    network.layers[i].neurons[j].connections.push(Connection{ from: [i-1,k], weight: 0.0, innovation });
+                }
+            }
+        }
+
+        network
+    }
+    fn connection_genes(&self) -> Vec<(usize,f32)>{
+        let mut genes: Vec<(usize,f32)> = self.layers.iter().flat_map(|layer| layer.neurons.iter().flat_map(|neuron| neuron.connections.iter().map(|connection| (connection.innovation, connection.weight)))).collect();
+        genes.sort_by_key(|gene| gene.0);
+        genes
+    }
+    fn add_connection(&mut self, innovations: &mut Innovations, rng: &mut SimRng){
+        let layer = rng.random_range(1..self.layers.len());
+        let from_layer = rng.random_range(0..layer);
+        if self.layers[layer].neurons.is_empty() || self.layers[from_layer].neurons.is_empty() { return; }
+
+        let neuron = rng.random_range(0..self.layers[layer].neurons.len());
+        let from = [from_layer, rng.random_range(0..self.layers[from_layer].neurons.len())];
+
+        if self.layers[layer].neurons[neuron].connections.iter().any(|connection| connection.from == from) { return; }
+
+        let innovation = innovations.connection(self.layers[from[0]].neurons[from[1]].id, self.layers[layer].neurons[neuron].id);
+        self.layers[layer].neurons[neuron].connections.push(Connection{ from, weight: rng.random_range(-1.0..=1.0), innovation });
+    }
+    fn remove_connection(&mut self, rng: &mut SimRng){
+        if let Some([layer, neuron]) = self.random_connected_neuron(rng) {
+            let connections = &mut self.layers[layer].neurons[neuron].connections;
+            connections.remove(rng.random_range(0..connections.len()));
+        }
+    }
+    //splits a connection in two with a new neuron, either in a fresh layer just before the destination or in an existing layer between the two ends
+    fn add_neuron(&mut self, new_layer: bool, innovations: &mut Innovations, rng: &mut SimRng){
+        let Some([mut layer, neuron]) = self.random_connected_neuron(rng) else { return; };
+        let c = rng.random_range(0..self.layers[layer].neurons[neuron].connections.len());
+        let connection = self.layers[layer].neurons[neuron].connections[c].clone();
+
+        let target = if new_layer || layer - connection.from[0] < 2 {
+            self.insert_layer(layer);
+            layer += 1;
+            layer - 1
+        }
+        else {
+            rng.random_range(connection.from[0]+1..layer)
+        };
+
+        let mut id = innovations.split(connection.innovation);
+        if self.layers.iter().any(|layer| layer.neurons.iter().any(|neuron| neuron.id == id)) {
+            id = innovations.node();
+        }
+
+        let from_id = self.layers[connection.from[0]].neurons[connection.from[1]].id;
+        let to_id = self.layers[layer].neurons[neuron].id;
+
+        let mut new_neuron = Neuron::zero(id);
+        new_neuron.connections.push(Connection{ from: connection.from, weight: 1.0, innovation: innovations.connection(from_id, id) });
+        self.layers[target].neurons.push(new_neuron);
+
+        self.layers[layer].neurons[neuron].connections[c] = Connection{
+            from: [target, self.layers[target].neurons.len() - 1],
+            weight: connection.weight,
+            innovation: innovations.connection(id, to_id),
+        };
+    }
+    fn remove_neuron(&mut self, rng: &mut SimRng){
+        if self.layers.len() < 3 { return; }
+
+        let layer = rng.random_range(1..self.layers.len()-1);
+        if self.layers[layer].neurons.is_empty() { return; }
+        let neuron = rng.random_range(0..self.layers[layer].neurons.len());
+
+        self.layers[layer].neurons.remove(neuron);
+        self.layers.iter_mut().skip(layer+1).for_each(|later| later.neurons.iter_mut().for_each(|later_neuron|{
+            later_neuron.connections.retain(|connection| connection.from != [layer, neuron]);
+            later_neuron.connections.iter_mut().for_each(|connection|{
+                if connection.from[0] == layer && connection.from[1] > neuron {
+                    connection.from[1] -= 1;
+                }
+            });
+        }));
+
+        if self.layers[layer].neurons.is_empty() {
+            self.layers.remove(layer);
+            self.layers.iter_mut().skip(layer).for_each(|later| later.neurons.iter_mut().for_each(|later_neuron|{
+                later_neuron.connections.iter_mut().for_each(|connection|{
+                    if connection.from[0] > layer {
+                        connection.from[0] -= 1;
+                    }
+                });
+            }));
+        }
+    }
+    fn insert_layer(&mut self, index: usize){
+        self.layers.insert(index, Layer{ neurons: vec![] });
+        self.layers.iter_mut().skip(index+1).for_each(|layer| layer.neurons.iter_mut().for_each(|neuron|{
+            neuron.connections.iter_mut().for_each(|connection|{
+                if connection.from[0] >= index {
+                    connection.from[0] += 1;
+                }
+            });
+        }));
+    }
+    fn random_connected_neuron(&self, rng: &mut SimRng) -> Option<[usize;2]>{
+        let connected: Vec<[usize;2]> = self.layers.iter().enumerate().skip(1).flat_map(|(i, layer)|{
+            layer.neurons.iter().enumerate().filter(|(_, neuron)| !neuron.connections.is_empty()).map(move |(j, _)| [i, j])
+        }).collect();
+
+        if connected.is_empty() { None } else { Some(connected[rng.random_range(0..connected.len())]) }
     }
 }
 impl Layer{
     pub fn activations(&self)->Vec<f32>{
         self.neurons.iter().map(|neuron| neuron.activation).collect()
     }
-    fn propagate(&mut self,inputs: Vec<f32>) -> Vec<f32>{
-        self.neurons.iter_mut()
-            .map(|neuron| neuron.propagate(&inputs)).collect()
+    fn propagate(&mut self, previous: &[Layer]){
+        self.neurons.iter_mut().for_each(|neuron| neuron.propagate(previous));
     }
-    fn random(input_size: usize, output_size: usize) -> Self{
-        let neurons = (0..output_size).map(|_| Neuron::random(input_size)).collect();
-
-        Self { neurons }
+    fn mutate(&mut self,strength: f32,probability: f32, rng: &mut SimRng){
+        self.neurons.iter_mut().for_each(|neuron| neuron.mutate(strength,probability,rng));
     }
-    fn zero(input_size: usize, output_size: usize) -> Self{
-        let neurons = (0..output_size).map(|_| Neuron::zero(input_size)).collect();
-        Self { neurons }
+}
+impl Neuron{
+    fn propagate(&mut self, previous: &[Layer]){
+        let input = self.connections.iter()
+            .map(|connection| previous[connection.from[0]].neurons[connection.from[1]].activation * connection.weight).sum::<f32>();
+        self.activation = self.function.apply(input+self.bias);
     }
-    fn mutate(&mut self,strength: f32,probability: f32){
-        self.neurons.iter_mut().for_each(|neuron| neuron.mutate(strength,probability));
+    fn zero(id: usize) -> Self {
+        Self { id, activation: 0.0, connections: vec![], bias: 0.0, function: ActivationFunction::Relu }
     }
-    fn compare(&self, other: &Layer) -> f32{
-        self.neurons.iter().zip(other.neurons.iter()).map(|neuron|{ neuron.0.compare(neuron.1) }).sum::<f32>()/self.neurons.len() as f32
+    fn mutate(&mut self,strength: f32,probability: f32, rng: &mut SimRng){
+        self.connections.iter_mut().for_each(|connection| if rng.random_bool(probability as f64){
+            connection.weight += rng.random_range(-strength..=strength);
+        });
+        if rng.random_bool(probability as f64){
+            self.bias += rng.random_range(-strength..=strength);
+        }
+        //swapping the activation function is far more disruptive than nudging a weight so it happens a tenth as often
+        if rng.random_bool(probability as f64 * 0.1){
+            self.function = ActivationFunction::ALL[rng.random_range(0..ActivationFunction::ALL.len())];
+        }
     }
 }
-impl Neuron{
-    fn propagate(&mut self,inputs: &[f32]) -> f32{
-        self.activation = 0.;
 
-        let input = inputs.iter().zip(&self.weights)
-            .map(|(input, weight)| input * weight).sum::<f32>();
-        self.activation = (input+self.bias).max(0.);
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::environment::animal::MEMORY_CELLS;
+    use crate::utilities::random::{stream, Stream};
 
-        self.activation
+    const LAYOUT: [usize;3] = [11 + MEMORY_CELLS, 11, 4 + MEMORY_CELLS];
+
+    //every connection has to come from a neuron that exists in an earlier layer, or propagate indexes out of bounds
+    fn assert_valid(network: &Network){
+        assert_eq!(network.layers[0].neurons.len(), LAYOUT[0]);
+        assert_eq!(network.layers.last().unwrap().neurons.len(), LAYOUT[2]);
+        assert!(network.layers[0].neurons.iter().all(|neuron| neuron.connections.is_empty()));
+        for (i, layer) in network.layers.iter().enumerate().skip(1) {
+            for neuron in &layer.neurons {
+                for connection in &neuron.connections {
+                    assert!(connection.from[0] < i);
+                    assert!(connection.from[1] < network.layers[connection.from[0]].neurons.len());
+                }
+            }
+        }
+        assert!(network.layers[1..network.layers.len()-1].iter().all(|layer| !layer.neurons.is_empty()));
+
+        let mut ids: Vec<usize> = network.layers.iter().flat_map(|layer| layer.neurons.iter().map(|neuron| neuron.id)).collect();
+        let count = ids.len();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), count);
     }
-    fn random(input_size: usize) -> Self {
-        let mut rng = rand::thread_rng();
 
-        let bias = rng.gen_range(-1.0..=1.0);
-        let weights = (0..input_size).map(|_| rng.gen_range(-1.0..=1.0)).collect();
+    #[test]
+    fn structural_mutations_keep_the_network_valid(){
+        let mut rng = stream(7, Stream::Animals);
+        let mut innovations = Innovations::default();
+        let mut network = Network::random(&LAYOUT, &mut innovations, &mut rng);
 
-        Self { activation: 0.0, weights, bias }
+        for _ in 0..5000 {
+            network.mutate_structure(0.5, &mut innovations, &mut rng);
+            network.mutate(0.1, 0.1, &mut rng);
+            assert_valid(&network);
+
+            network.input((0..LAYOUT[0]).map(|_| rng.random_range(-1.0..=1.0)).collect());
+            assert_eq!(network.propagate().len(), LAYOUT[2]);
+        }
+        assert!(network.layers.len() > LAYOUT.len());
     }
-    fn zero(input_size: usize) -> Self {
-        let weights = vec![0.;input_size];
-        Self { activation: 0.0, weights, bias: 0.0 }
+
+    #[test]
+    fn a_network_is_identical_to_itself(){
+        let mut rng = stream(7, Stream::Animals);
+        let mut innovations = Innovations::default();
+        let mut network = Network::random(&LAYOUT, &mut innovations, &mut rng);
+        assert_eq!(network.compare(&network), 0.);
+
+        for _ in 0..200 {
+            network.mutate_structure(0.5, &mut innovations, &mut rng);
+        }
+        assert_eq!(network.compare(&network), 0.);
     }
-    fn mutate(&mut self,strength: f32,probability: f32){
-        let mut rng = rand::thread_rng();
-        self.weights.iter_mut().for_each(|weight| if rng.gen_bool(probability as f64){
-            *weight += rng.gen_range(-strength..=strength);
-        });
-        if rng.gen_bool(probability as f64){
-            self.bias += rng.gen_range(-strength..=strength);
+
+    //a and b share their starting innovations, with b's structure grown further and every weight and bias redrawn
+    fn relatives(rng: &mut SimRng) -> (Network, Network){
+        let mut innovations = Innovations::default();
+        let a = Network::random(&LAYOUT, &mut innovations, rng);
+        let mut b = a.clone();
+        for _ in 0..50 {
+            b.mutate_structure(0.5, &mut innovations, rng);
         }
+        b.mutate(1.0, 1.0, rng);
+        (a, b)
     }
-    fn compare(&self, other: &Neuron) -> f32{
-        let weights_diff = if !self.weights.is_empty(){self.weights.iter().zip(other.weights.iter()).map(|weight| { (weight.0 - weight.1).abs() }).sum::<f32>()/self.weights.len() as f32}else{0.};
-        let bias_diff = (self.bias-other.bias).abs();
-        weights_diff+bias_diff/2.0
+
+    #[test]
+    fn crossover_takes_each_gene_from_a_parent(){
+        let mut rng = stream(7, Stream::Animals);
+        let (a, b) = relatives(&mut rng);
+        let weights: HashMap<usize,f32> = b.layers.iter().flat_map(|layer| layer.neurons.iter().flat_map(|neuron| neuron.connections.iter().map(|connection| (connection.innovation, connection.weight)))).collect();
+        let biases: HashMap<usize,f32> = b.layers.iter().flat_map(|layer| layer.neurons.iter().map(|neuron| (neuron.id, neuron.bias))).collect();
+
+        let child = a.crossover(&b, &mut rng);
+        assert_eq!(child.layers.len(), a.layers.len());
+        let (mut from_a, mut from_b) = (0, 0);
+        for (layer, a_layer) in child.layers.iter().zip(&a.layers) {
+            assert_eq!(layer.neurons.len(), a_layer.neurons.len());
+            for (neuron, a_neuron) in layer.neurons.iter().zip(&a_layer.neurons) {
+                assert_eq!(neuron.id, a_neuron.id);
+                assert!(neuron.bias == a_neuron.bias || biases.get(&neuron.id) == Some(&neuron.bias));
+                for (connection, a_connection) in neuron.connections.iter().zip(&a_neuron.connections) {
+                    assert_eq!(connection.from, a_connection.from);
+                    if connection.weight == a_connection.weight { from_a += 1; }
+                    else if weights.get(&connection.innovation) == Some(&connection.weight) { from_b += 1; }
+                    else { panic!("weight of innovation {} came from neither parent", connection.innovation); }
+                }
+            }
+        }
+        assert!(from_a > 0 && from_b > 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn compare_is_symmetric(){
+        let mut rng = stream(7, Stream::Animals);
+        let (a, b) = relatives(&mut rng);
+
+        assert!(a.compare(&b) > 0.);
+        assert!((a.compare(&b) - b.compare(&a)).abs() < 1e-5);
+    }
+}
@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 use crate::environment::collisions::{CELL_SIZE, Collisions, DIV};
 use crate::environment::rocks::RockMap;
 use crate::rendering::instance::Instance;
+use crate::utilities::random::{SimRng, Stream, stream};
 use crate::utilities::simulation_parameters::SimParams;
 
 #[derive(Clone,Serialize,Deserialize)]
@@ -16,6 +17,7 @@ pub struct Plant{
 pub struct Plants{
     pub plants: Vec<Plant>,
     pub bodies: Vec<Instance>,
+    rng: SimRng,
 }
 #[derive(Clone,Serialize,Deserialize)]
 pub struct PlantSpawners{
@@ -27,11 +29,11 @@ impl PlantSpawners {
             plants.spawn_near(rock_map,collisions,sim_params,spawner.position[0],spawner.position[1]);
         })
     }
-    pub fn random(&mut self,sim_params: &SimParams){
+    pub fn random(&mut self,sim_params: &SimParams,rng: &mut SimRng){
         if sim_params.world.plant_spawners == 0 {return;}
         for _ in 0..sim_params.world.plant_spawners{
-            let x = rand::thread_rng().gen_range(0..(sim_params.world.width*DIV-1.) as u32);
-            let y = rand::thread_rng().gen_range(0..(sim_params.world.height*DIV-1.) as u32);
+            let x = rng.random_range(0..(sim_params.world.width*DIV-1.) as u32);
+            let y = rng.random_range(0..(sim_params.world.height*DIV-1.) as u32);
 
             self.bodies.push(Instance::new([x as f32 * CELL_SIZE+CELL_SIZE*0.5,y as f32 * CELL_SIZE+CELL_SIZE*0.5],[0.0, 0.7, 0.0],PI/4.,CELL_SIZE * 0.9));
         }
@@ -60,10 +62,11 @@ impl PlantSpawners {
     }
 }
 impl Plants {
-    pub fn genesis()->Self{
+    pub fn genesis(seed: u64)->Self{
         Self{
             plants: vec![],
             bodies: vec![],
+            rng: stream(seed, Stream::Plants),
         }
     }
     pub fn remove(&mut self, i: usize){
@@ -93,8 +96,8 @@ impl Plants {
 
     pub fn spawn_random(&mut self,rock_map: &RockMap, collisions: &Collisions, sim_params: &SimParams){
         for _trials in 0..100{
-            let x = rand::thread_rng().gen_range(0.0..sim_params.world.width);
-            let y = rand::thread_rng().gen_range(0.0..sim_params.world.height);
+            let x = self.rng.random_range(0.0..sim_params.world.width);
+            let y = self.rng.random_range(0.0..sim_params.world.height);
 
             let mut spawn = true;
 
@@ -117,14 +120,13 @@ impl Plants {
     }
 
     pub fn spawn_near(&mut self,rock_map: &RockMap, collisions: &Collisions, sim_params: &SimParams,sx: f32, sy: f32){
-        let mut rng = rand::thread_rng();
         for _trials in 0..10{
-            let x = (sx + rand::thread_rng().gen_range(-sim_params.plants.spawn_radius..=sim_params.plants.spawn_radius)).clamp(0.,sim_params.world.width);
-            let y = (sy + rand::thread_rng().gen_range(-sim_params.plants.spawn_radius..=sim_params.plants.spawn_radius)).clamp(0.,sim_params.world.height);
+            let x = (sx + self.rng.random_range(-sim_params.plants.spawn_radius..=sim_params.plants.spawn_radius)).clamp(0.,sim_params.world.width);
+            let y = (sy + self.rng.random_range(-sim_params.plants.spawn_radius..=sim_params.plants.spawn_radius)).clamp(0.,sim_params.world.height);
 
             let mut spawn = true;
 
-            if !rng.gen_bool((((x-sx).powf(2.) + (y-sy).powf(2.))/(sim_params.plants.spawn_radius*sim_params.plants.spawn_radius)).min(1.0) as f64) {
+            if !self.rng.random_bool((((x-sx).powf(2.) + (y-sy).powf(2.))/(sim_params.plants.spawn_radius*sim_params.plants.spawn_radius)).min(1.0) as f64) {
                 'outer: for m in -1..=1{
                     for n in -1..=1{
                         let i = (x * DIV + m as f32) as usize * collisions.cells_height + (y * DIV + n as f32) as usize;
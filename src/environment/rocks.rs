@@ -1,4 +1,4 @@
-use rand::{Rng, thread_rng};
+use rand::Rng;
 use rayon::iter::IndexedParallelIterator;
 use rayon::iter::IntoParallelRefIterator;
 use rayon::iter::ParallelIterator;
@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 use simdnoise::NoiseBuilder;
 use crate::environment::collisions::{CELL_SIZE, DIV};
 use crate::rendering::instance::Instance;
+use crate::utilities::random::SimRng;
 
 #[derive(Serialize, Deserialize,Clone)]
 pub struct RockMap{
@@ -35,8 +36,8 @@ impl RockMap{
             height,
         }
     }
-    pub fn randomise(&mut self){
-        let seed = thread_rng().gen_range(-100000..100000);
+    pub fn randomise(&mut self, rng: &mut SimRng){
+        let seed = rng.random_range(-100000..100000);
 
         let high_freq_noise = NoiseBuilder::fbm_2d_offset(seed as f32, self.width, seed as f32, self.height)
             .with_seed(seed).with_freq(0.1)
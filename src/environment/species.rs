@@ -3,27 +3,56 @@ use crate::environment::animal::Animal;
 use crate::utilities::simulation_parameters::SimParams;
 
 #[derive(Serialize,Deserialize,Clone)]
-struct Species{
-    parent: usize,
-    specimen: Animal,
-    count: usize,
+pub struct Species{
+    pub parent: usize,
+    pub specimen: Animal,
+    //every animal ever born into the species
+    pub count: usize,
+    #[serde(default)]
+    pub birth_step: i32,
+    #[serde(default)]
+    pub extinction_step: Option<i32>,
+    //animals and eggs alive at the last census
+    #[serde(default)]
+    pub population: usize,
+    //(step, population) recorded whenever the population changes
+    #[serde(default)]
+    pub history: Vec<(i32,usize)>,
 }
 impl Species{
     fn compare(&self, animal: &Animal) -> f32{
-        self.specimen.brain.network.compare(&animal.brain.network)
+        self.specimen.brain.network.compare(&animal.brain.network) + self.specimen.genome.compare(&animal.genome)
     }
 }
+
+#[derive(Serialize)]
+struct PhylogenyNode<'a>{
+    id: usize,
+    parent: usize,
+    birth_step: i32,
+    extinction_step: Option<i32>,
+    count: usize,
+    population: usize,
+    history: &'a [(i32,usize)],
+}
+
 #[derive(Default,Serialize,Deserialize,Clone)]
 pub struct SpeciesList{
     species: Vec<Species>,
+    #[serde(default)]
+    step: i32,
 }
 impl SpeciesList{
     pub fn speciate(&mut self, child: &Animal,parent_species_id: usize,sim_params: &SimParams) -> usize{
-        if parent_species_id == 0 || self.species[parent_species_id -1].specimen.brain.network.compare(&child.brain.network) > sim_params.animals.speciation_threshold{
+        if parent_species_id == 0 || self.species[parent_species_id -1].compare(child) > sim_params.animals.speciation_threshold{
             let new_species = Species{
                 parent: parent_species_id,
                 specimen: child.clone(),
                 count: 1,
+                birth_step: self.step,
+                extinction_step: None,
+                population: 1,
+                history: vec![(self.step, 1)],
             };
             self.species.push(new_species);
             self.species.len()
@@ -33,7 +62,127 @@ impl SpeciesList{
             parent_species_id
         }
     }
+    pub fn set_step(&mut self, step: i32){
+        self.step = step;
+    }
+    //recounts the living members of every species, eggs included, marking species with none left as extinct
+    pub fn census<'a>(&mut self, animals: impl Iterator<Item = &'a Animal>){
+        let mut populations = vec![0; self.species.len()];
+        animals.filter(|animal| animal.species_id > 0).for_each(|animal| populations[animal.species_id - 1] += 1);
+
+        let step = self.step;
+        self.species.iter_mut().zip(populations).for_each(|(species, population)|{
+            if species.population != population {
+                species.population = population;
+                species.history.push((step, population));
+            }
+            if population == 0 && species.extinction_step.is_none() {
+                species.extinction_step = Some(step);
+            }
+        });
+    }
     pub fn count(&self)->usize{
         self.species.len()
     }
-}
\ No newline at end of file
+    pub fn get(&self, id: usize) -> Option<&Species>{
+        id.checked_sub(1).and_then(|i| self.species.get(i))
+    }
+    //ids of the daughter species of each species, in order of appearance, with the founding species under index 0
+    pub fn daughters(&self) -> Vec<Vec<usize>>{
+        let mut daughters = vec![vec![]; self.species.len() + 1];
+        self.species.iter().enumerate().for_each(|(i, species)| daughters[species.parent].push(i + 1));
+        daughters
+    }
+    //species ids ordered so that every species comes directly after its parent or an earlier sister's descendants
+    pub fn depth_first(&self) -> Vec<usize>{
+        let daughters = self.daughters();
+
+        let mut order = vec![];
+        let mut stack: Vec<usize> = daughters[0].iter().rev().copied().collect();
+        while let Some(id) = stack.pop() {
+            order.push(id);
+            stack.extend(daughters[id].iter().rev());
+        }
+        order
+    }
+    //the species tree in Newick format with branch lengths in simulated minutes, each daughter species branching off its parent's lineage at the step it appeared and founding species starting at theirs
+    pub fn to_newick(&self) -> String{
+        let daughters = self.daughters();
+        //an empty tree, as "();" isn't valid newick
+        if daughters[0].is_empty() {
+            return ";".to_string();
+        }
+        let roots: Vec<String> = daughters[0].iter().map(|id| self.newick_lineage(*id, self.species[id - 1].birth_step, &daughters[*id], &daughters)).collect();
+        ["(", &roots.join(","), ");"].join("")
+    }
+    fn newick_lineage(&self, id: usize, from: i32, remaining: &[usize], daughters: &[Vec<usize>]) -> String{
+        let species = &self.species[id - 1];
+
+        match remaining.split_first() {
+            None => format!("S{}:{}", id, minutes(species.extinction_step.unwrap_or(self.step) - from)),
+            Some((daughter, rest)) => {
+                let split = self.species[daughter - 1].birth_step;
+                format!("({},{}):{}", self.newick_lineage(*daughter, split, &daughters[*daughter], daughters), self.newick_lineage(id, split, rest, daughters), minutes(split - from))
+            }
+        }
+    }
+    pub fn to_json(&self) -> String{
+        let nodes: Vec<PhylogenyNode> = self.species.iter().enumerate().map(|(i, species)| PhylogenyNode{
+            id: i + 1,
+            parent: species.parent,
+            birth_step: species.birth_step,
+            extinction_step: species.extinction_step,
+            count: species.count,
+            population: species.population,
+            history: &species.history,
+        }).collect();
+        serde_json::to_string(&nodes).unwrap()
+    }
+}
+
+fn minutes(steps: i32) -> f32{
+    steps as f32 / 3600.
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::environment::animal::Animals;
+
+    fn species(parent: usize, birth_step: i32, extinction_step: Option<i32>) -> Species{
+        let mut animals = Animals::genesis(0);
+        animals.spawn(&SimParams::default());
+        Species{
+            parent,
+            specimen: animals.animals[0].clone(),
+            count: 1,
+            birth_step,
+            extinction_step,
+            population: 0,
+            history: vec![],
+        }
+    }
+
+    #[test]
+    fn empty_tree(){
+        assert_eq!(SpeciesList::default().to_newick(), ";");
+    }
+
+    #[test]
+    fn single_species(){
+        let list = SpeciesList{ species: vec![species(0, 0, None)], step: 7200 };
+        assert_eq!(list.to_newick(), "(S1:2);");
+    }
+
+    #[test]
+    fn nested_tree(){
+        let list = SpeciesList{ species: vec![species(0, 0, None), species(1, 3600, None), species(0, 0, Some(3600))], step: 7200 };
+        assert_eq!(list.to_newick(), "((S2:1,S1:1):1,S3:1);");
+    }
+
+    #[test]
+    fn late_founders_start_when_they_appear(){
+        let list = SpeciesList{ species: vec![species(0, 3600, None), species(1, 5400, None)], step: 7200 };
+        assert_eq!(list.to_newick(), "((S2:0.5,S1:0.5):0.5);");
+    }
+}
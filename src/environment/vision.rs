@@ -6,7 +6,7 @@ use crate::environment::collisions::{CELL_SIZE, Collisions, DIV};
 use crate::environment::rocks::RockMap;
 use crate::rendering::instance::Instance;
 
-#[derive(Clone,Serialize,Deserialize)]
+#[derive(Default,Clone,Serialize,Deserialize)]
 pub struct SensoryInput{
     pub animal_vision: f32,
     pub plant_vision: f32,
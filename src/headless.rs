@@ -0,0 +1,98 @@
+use std::fs;
+use std::path::Path;
+use std::process;
+use ecosystem_simulation::utilities::export::export_stats;
+use ecosystem_simulation::utilities::save_system::{load_file, SaveSystem};
+use ecosystem_simulation::utilities::simulation_parameters::SimParams;
+use ecosystem_simulation::world::World;
+
+pub const DEFAULT_STEPS: i32 = 3600 * 60;
+
+//runs a fresh world for the given number of steps without creating a window, writing autosaves and stats to disk
+//stats are also exported every export_interval simulated minutes if one is given, and the final save is also written as json if json_save is set
+//with resume the run carries on from the newest autosave in the output directory, starting fresh if there isn't one
+pub fn run(sim_params: SimParams, steps: i32, export_interval: Option<i32>, json_save: bool, resume: bool, output: &Path){
+    let saves_dir = output.join("saves");
+    let stats_dir = output.join("stats");
+    fs::create_dir_all(&saves_dir).expect("Unable to create saves directory");
+    fs::create_dir_all(&stats_dir).expect("Unable to create stats directory");
+
+    let mut save_system = SaveSystem::new(&saves_dir);
+    let mut world = if resume {
+        match save_system.load_latest_autosave() {
+            Ok(save) => World::from_save(save),
+            Err(err) => {
+                eprintln!("Unable to resume, starting a new world: {}", err);
+                World::new(sim_params)
+            }
+        }
+    }
+    else {
+        World::new(sim_params)
+    };
+
+    println!("seed: {}", world.sim_params().world.seed);
+
+    while world.current_step() < steps {
+        let step = world.current_step();
+
+        if step % (world.sim_params().autosave * 3600) == 0 && step > 0 {
+            let keep = world.sim_params().autosave_keep;
+            let saved = save_system.autosave(world.to_save(), keep);
+            save_system.report(saved);
+        }
+
+        //one graph sample per simulated second, matching the windowed runner at 60 fps
+        if step % 60 == 0 {
+            world.record_stats();
+        }
+
+        if export_interval.is_some_and(|interval| step % (interval.max(1) * 3600) == 0 && step > 0) {
+            export_stats(world.stats(), &stats_dir, step).expect("Unable to export stats");
+        }
+
+        if step % 3600 == 0 {
+            println!("step: {} animals: {} plants: {} fruit: {} species: {}", step, world.animals().count(), world.plants().count(), world.fruit().count(), world.species_list().count());
+        }
+
+        world.step();
+    }
+
+    let saved = save_system.save(world.to_save());
+    save_system.flush();
+    let saved = save_system.report(saved);
+    if let (Some(file), true) = (saved, json_save) {
+        let exported = save_system.export_json(&file);
+        save_system.report(exported);
+    }
+
+    export_stats(world.stats(), &stats_dir, steps).expect("Unable to export stats");
+    fs::write(stats_dir.join(["phylogeny_",&steps.to_string(),".nwk"].join("")), world.species_list().to_newick()).expect("Unable to write file");
+    fs::write(stats_dir.join(["phylogeny_",&steps.to_string(),".json"].join("")), world.species_list().to_json()).expect("Unable to write file");
+    fs::write(stats_dir.join(["events_",&steps.to_string(),".jsonl"].join("")), world.log().to_json_lines()).expect("Unable to write file");
+}
+
+//re-runs a save from its start up to the step it was saved at, exiting with an error if it played out differently
+pub fn replay(path: &Path){
+    let mut world = match load_file(path).and_then(World::replay) {
+        Ok(world) => world,
+        Err(err) => {
+            eprintln!("Unable to replay {}: {}", path.display(), err);
+            process::exit(1);
+        }
+    };
+    let end_step = world.replay_status().map(|replay| replay.end_step).unwrap_or_default();
+
+    println!("seed: {}", world.sim_params().world.seed);
+    while world.current_step() < end_step {
+        world.step();
+    }
+
+    match world.replay_status().and_then(|replay| replay.divergence.as_ref()) {
+        Some(divergence) => {
+            eprintln!("Replay {}", divergence);
+            process::exit(1);
+        }
+        None => println!("Replay matched all {} events up to step {}", world.log().len(), end_step),
+    }
+}
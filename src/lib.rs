@@ -0,0 +1,4 @@
+pub mod rendering;
+pub mod utilities;
+pub mod environment;
+pub mod world;
@@ -1,10 +1,8 @@
 
-mod rendering;
-mod utilities;
-mod environment;
+mod cli;
+mod headless;
 
-use std::ops::Index;
-use rendering::render::Renderer;
+use ecosystem_simulation::rendering::render::Renderer;
 
 use winit::event::WindowEvent;
 
@@ -14,43 +12,49 @@ use winit::{
     keyboard::Key,
     window::WindowBuilder,
 };
+use std::path::Path;
 use std::sync::Arc;
 use std::time::SystemTime;
+use clap::Parser;
 use sysinfo::System;
 use winit::dpi::PhysicalSize;
 use winit::platform::modifier_supplement::KeyEventExtModifierSupplement;
-use crate::environment::animal::Animals;
-use crate::environment::collisions::{DIV};
-use crate::environment::fruit::FruitSpawners;
-use crate::environment::plants::PlantSpawners;
-use crate::environment::rocks::RockMap;
-use crate::environment::temperature::TemperatureMap;
-use crate::rendering::camera::Camera;
-use crate::utilities::highlighter::Highlighter;
-use crate::utilities::input_manager::Inputs;
-use crate::utilities::save_system::SaveSystem;
-use crate::utilities::simulation_parameters::Pen;
-use crate::utilities::state::State;
+use ecosystem_simulation::rendering::camera::Camera;
+use ecosystem_simulation::utilities::highlighter::Highlighter;
+use ecosystem_simulation::utilities::input_manager::Inputs;
+use ecosystem_simulation::utilities::save_system::SaveSystem;
+use ecosystem_simulation::utilities::simulation_parameters::{Pen, SimParams};
+use ecosystem_simulation::utilities::state::State;
+use ecosystem_simulation::utilities::timeline::Timeline;
+use ecosystem_simulation::world::World;
 
 fn main() {
-    pollster::block_on(run());
+    let args = cli::Args::parse();
+    let sim_params = args.sim_params();
+
+    if let Some(path) = &args.write_config {
+        sim_params.save(path).expect("Unable to write config");
+    }
+    else if let Some(path) = &args.replay {
+        headless::replay(path);
+    }
+    else if args.headless {
+        headless::run(sim_params, args.steps, args.export_interval, args.json_save, args.resume, &args.output);
+    }
+    else {
+        pollster::block_on(run(sim_params, &args.output));
+    }
 }
 
-pub async fn run() {
+pub async fn run(base_params: SimParams, output: &Path) {
     let event_loop = EventLoop::new().unwrap();
     let window = Arc::new(WindowBuilder::new().with_title("EcoSim").with_inner_size(PhysicalSize::new(1200, 800)).build(&event_loop).unwrap());
    // let window = Arc::new(ActiveEventLoop::create_window().unwrap());
 
-    let mut save_syatem = SaveSystem::default();
-    let mut renderer = Renderer::new(window).await;
-    let mut step = 0;
-    let mut animals = Animals::genesis();
-    let mut plants = environment::plants::Plants::genesis();
-    let mut fruit = environment::fruit::Fruits::genesis();
-    let mut stats = utilities::statistics::Stats::default();
-    let mut eggs = environment::eggs::Eggs::default();
-    let mut sim_params = utilities::simulation_parameters::SimParams::default();
-    let mut species_list = environment::species::SpeciesList::default();
+    let mut save_syatem = SaveSystem::new(&output.join("saves"));
+    let mut renderer = Renderer::new(window, output.join("stats")).await;
+    let mut sim_params = base_params.clone();
+    let mut world = World::new(sim_params.clone());
     let mut graph_timer = SystemTime::now();
     let mut diagnostic_timer = SystemTime::now();
     let mut inputs = Inputs::default();
@@ -62,17 +66,11 @@ pub async fn run() {
         zoom: 0.05,
         ratio: 1.0,
     };
-    let mut collisions = environment::collisions::Collisions::new(&sim_params);
     let mut inspected_animal_id = 0;
     let mut inspected_animal = None;
     let mut follow = false;
-    let mut rocks = RockMap::new(collisions.cells_height);
-    let mut plant_spawners = PlantSpawners{ bodies: vec![] };
-    plant_spawners.random(&sim_params);
-    let mut fruit_spawners = FruitSpawners{ bodies: vec![] };
-    fruit_spawners.random(&sim_params);
     let mut highlighter = Highlighter::default();
-    let mut temp_map = TemperatureMap::new(collisions.cells_height);
+    let mut timeline = Timeline::default();
 
     let _ = event_loop.run(move |event, ewlt| match event {
         Event::WindowEvent {
@@ -80,7 +78,10 @@ pub async fn run() {
             window_id,
         } if window_id == renderer.window().id() => {
             match event {
-                WindowEvent::CloseRequested => ewlt.exit(),
+                WindowEvent::CloseRequested => {
+                    save_syatem.flush();
+                    ewlt.exit()
+                },
                 WindowEvent::KeyboardInput { event, .. } => {
                     if state != State::Menu && event.state == ElementState::Pressed && !event.repeat {
                         match event.key_without_modifiers().as_ref() {
@@ -123,8 +124,11 @@ pub async fn run() {
                     renderer.resize(Some(*physical_size));
                 }
                 WindowEvent::RedrawRequested => {
+                    save_syatem.poll();
+
                     match state {
                         State::Exit =>{
+                            save_syatem.flush();
                             ewlt.exit()
                         }
                         State::Menu | State::CreateSim => {
@@ -138,191 +142,111 @@ pub async fn run() {
                             }
                         }
                         State::LoadSave =>{
-                            (step, animals, plants, fruit, eggs, species_list, stats, sim_params, rocks,fruit_spawners,plant_spawners) = save_syatem.load(sim_params.save_id).open();
-                            collisions = environment::collisions::Collisions::new(&sim_params);
-                            collisions.update_animal_grid(animals.instances().as_slice());
-                            collisions.update_plant_grid(plants.instances());
-                            collisions.update_fruit_grid(fruit.instances());
-
-                            temp_map = TemperatureMap::new(collisions.cells_height);
-                            temp_map.set(sim_params.temp.plant_spawner_temp, plant_spawners.instances());
-                            temp_map.set(sim_params.temp.fruit_spawner_temp, fruit_spawners.instances());
-                            temp_map.update(sim_params.temp.spread,sim_params.temp.smooth,&rocks.rocks);
-
-                            state = State::RunSim;
+                            let save = save_syatem.load(&sim_params.save_file);
+                            match save_syatem.report(save) {
+                                Some(save) => {
+                                    world = World::from_save(save);
+                                    timeline.clear();
+                                    state = State::RunSim;
+                                }
+                                None => state = State::Menu,
+                            }
+                        }
+                        State::ReplaySave =>{
+                            let save = save_syatem.load(&sim_params.save_file).and_then(World::replay);
+                            match save_syatem.report(save) {
+                                Some(replay) => {
+                                    world = replay;
+                                    timeline.clear();
+                                    highlighter = Highlighter::default();
+                                    state = State::RunSim;
+                                }
+                                None => state = State::Menu,
+                            }
+                        }
+                        State::ResumeAutosave =>{
+                            let save = save_syatem.load_latest_autosave();
+                            match save_syatem.report(save) {
+                                Some(save) => {
+                                    world = World::from_save(save);
+                                    timeline.clear();
+                                    highlighter = Highlighter::default();
+                                    state = State::RunSim;
+                                }
+                                None => state = State::Menu,
+                            }
                         }
                         State::NewSim =>{
                             sim_params.world.height = sim_params.world.width;
                             let world_settings = sim_params.world.clone();
-                            sim_params = utilities::simulation_parameters::SimParams::default();
+                            sim_params = base_params.clone();
                             sim_params.world = world_settings;
 
                             camera.position = [sim_params.world.width/2.0,sim_params.world.height/2.0];
 
-                            step = 0;
-                            stats = utilities::statistics::Stats::default();
-
-                            collisions = environment::collisions::Collisions::new(&sim_params);
-                            animals = Animals::genesis();
-                            eggs = environment::eggs::Eggs::default();
-                            plants = environment::plants::Plants::genesis();
-                            fruit = environment::fruit::Fruits::genesis();
-                            species_list = environment::species::SpeciesList::default();
-
-                            rocks = RockMap::new(collisions.cells_height);
-                            plant_spawners = PlantSpawners{ bodies: vec![] };
-                            fruit_spawners = FruitSpawners{ bodies: vec![] };
-
-                            if sim_params.world.generate_terrain{
-                                rocks.randomise();
-                            }
-
-                            fruit_spawners.random(&sim_params);
-                            plant_spawners.random(&sim_params);
-
+                            world = World::new(sim_params.clone());
+                            timeline.clear();
                             highlighter = Highlighter::default();
 
-                            temp_map = TemperatureMap::new(collisions.cells_height);
-                            temp_map.set(sim_params.temp.plant_spawner_temp, plant_spawners.instances());
-                            temp_map.set(sim_params.temp.fruit_spawner_temp, fruit_spawners.instances());
-                            temp_map.update(sim_params.temp.spread,sim_params.temp.smooth,&rocks.rocks);
-
                             state = State::RunSim;
                         }
                         State::SaveSim => {
-                            animals.kill();
-                            plants.kill();
-                            fruit.kill();
-                            save_syatem.save(step, animals.clone(), plants.clone(), fruit.clone(), eggs.clone(), species_list.clone(),stats.clone(),sim_params.clone(),rocks.clone(),fruit_spawners.clone(),plant_spawners.clone());
-                            collisions.update_animal_grid(animals.instances().as_slice());
-                            collisions.update_plant_grid(plants.instances());
-                            collisions.update_fruit_grid(fruit.instances());
-                            highlighter.set_highlights(&animals);
+                            //a failed save is shown in the main menu rather than ending the run
+                            let saved = save_syatem.save(world.to_save());
+                            save_syatem.report(saved);
 
                             state = State::RunSim;
                         }
                         State::RunSim=>{
-                            inspected_animal = animals.animals.iter().find(|animal|{
+                            inspected_animal = world.animals().animals.iter().find(|animal|{
                                 animal.id == inspected_animal_id
                             }).cloned();
 
                             if diagnostic_timer.elapsed().unwrap().as_millis() >= 1000 {
-                                stats.update_diagnostics(frames, &mut system);
+                                world.stats_mut().update_diagnostics(frames, &mut system);
                                 frames = 0;
                                 diagnostic_timer = SystemTime::now();
                             }
 
-                            if sim_params.build.pen == Pen::None {
-                                for _ in 0..sim_params.simulation.steps_per_frame {
-                                    if step % (sim_params.autosave * 3600) == 0 && step > 0 {
-                                        state = State::SaveSim;
+                            if world.sim_params().build.pen == Pen::None {
+                                for _ in 0..world.sim_params().simulation.steps_per_frame {
+                                    let snapshot = timeline.update(&world, &save_syatem);
+                                    save_syatem.report(snapshot);
+
+                                    let step = world.current_step();
+                                    if step % (world.sim_params().autosave * 3600) == 0 && step > 0 {
+                                        let keep = world.sim_params().autosave_keep;
+                                        let saved = save_syatem.autosave(world.to_save(), keep);
+                                        save_syatem.report(saved);
                                     }
 
-                                    if graph_timer.elapsed().unwrap().as_millis() >= 1000 / sim_params.simulation.steps_per_frame as u128 {
-                                        stats.update_graphs(animals.count(), fruit.count(), plants.count(), &animals.animals);
+                                    if graph_timer.elapsed().unwrap().as_millis() >= 1000 / world.sim_params().simulation.steps_per_frame as u128 {
+                                        world.record_stats();
                                         graph_timer = SystemTime::now();
                                     }
 
-                                    if step % 3600 == 0 {
-                                        for _ in 0..sim_params.plants.spawn_rate {
-                                            plant_spawners.spawn(&mut plants, &rocks, &collisions, &sim_params);
-                                        }
-                                        for _ in 0..sim_params.fruit.spawn_rate {
-                                            fruit_spawners.spawn(&mut fruit, &rocks, &collisions, &sim_params);
-                                        }
-
-                                        for _ in 0..sim_params.plants.global_spawn_rate {
-                                            plants.spawn_random(&rocks, &collisions, &sim_params);
-                                        }
-                                        for _ in 0..sim_params.fruit.global_spawn_rate {
-                                            fruit.spawn_random(&rocks, &collisions, &sim_params);
-                                        }
-
-                                        if animals.count() < 40 {
-                                            for _ in 0..20{
-                                                animals.spawn(&sim_params);
-                                            }
-                                        }
-                                    }
+                                    world.step();
 
                                     if step % 6 == 0 {
-                                        animals.kill();
-                                        plants.kill();
-                                        fruit.kill();
-                                        collisions.update_animal_grid(animals.instances().as_slice());
-                                        collisions.update_plant_grid(plants.instances());
-                                        collisions.update_fruit_grid(fruit.instances());
-                                        highlighter.set_highlights(&animals);
+                                        highlighter.set_highlights(world.animals());
                                     }
 
-                                    collisions.handle_collisions(&mut animals, &mut plants, &mut fruit, &sim_params);
-                                    eggs.update(&mut animals);
-                                    animals.update(&mut plants,&mut fruit, &mut eggs, &mut sim_params, &collisions, &mut species_list, &rocks,&temp_map);
-
-                                    highlighter.move_highlights(&animals);
-
-                                    step += 1;
+                                    highlighter.move_highlights(world.animals());
                                 }
                             }
                             if !renderer.egui_context().is_pointer_over_area(){
-                                if sim_params.build.pen == Pen::None{
+                                if world.sim_params().build.pen == Pen::None{
                                     if inputs.left_mouse {
-                                        let pos = camera.screen_to_world_pos(inputs.mouse_pos);
-                                        if pos[0] > 0. && pos[0] < sim_params.world.width && pos[1] >0. && pos[1] < sim_params.world.height {
-                                            if let Some(i) = collisions.animals_grid[(pos[0] * DIV) as usize * collisions.cells_height + (pos[1] * DIV) as usize].object_ids.last() {
-                                                let animal = animals.animals.index(*i);
-                                                inspected_animal_id = animal.id;
-                                                follow = true;
-                                            }
+                                        if let Some(animal) = world.animal_at(camera.screen_to_world_pos(inputs.mouse_pos)) {
+                                            inspected_animal_id = animal.id;
+                                            follow = true;
                                         }
                                     }
                                 }
                                 else if inputs.left_mouse {
-                                    let update = match sim_params.build.pen {
-                                        Pen::Rock=>{
-                                            rocks.set(1, camera.screen_to_world_pos(inputs.mouse_pos), sim_params.build.pen_size)
-                                        },
-                                        Pen::PlantGenerator=>{
-                                            plant_spawners.place(camera.screen_to_world_pos(inputs.mouse_pos),&sim_params)
-                                        },
-                                        Pen::FruitGenerator=>{
-                                            fruit_spawners.place(camera.screen_to_world_pos(inputs.mouse_pos),&sim_params)
-                                        },
-                                        Pen::None=>{ false }
-                                    };
-
-                                    if update {
-                                        plants.remove_plants_in_walls(&rocks);
-                                        fruit.remove_plants_in_walls(&rocks);
-
-                                        collisions.update_plant_grid(plants.instances());
-                                        collisions.update_fruit_grid(fruit.instances());
-
-                                        temp_map.clear();
-                                        temp_map.set(sim_params.temp.plant_spawner_temp, plant_spawners.instances());
-                                        temp_map.set(sim_params.temp.fruit_spawner_temp, fruit_spawners.instances());
-                                        temp_map.update(sim_params.temp.spread,sim_params.temp.smooth,&rocks.rocks);
-                                    }
+                                    world.paint(camera.screen_to_world_pos(inputs.mouse_pos), true);
                                 } else if inputs.right_mouse {
-                                    let update = match sim_params.build.pen {
-                                        Pen::Rock=>{
-                                            rocks.set(0, camera.screen_to_world_pos(inputs.mouse_pos), sim_params.build.pen_size)
-                                        },
-                                        Pen::PlantGenerator=>{
-                                            plant_spawners.remove(camera.screen_to_world_pos(inputs.mouse_pos))
-                                        },
-                                        Pen::FruitGenerator=>{
-                                            fruit_spawners.remove(camera.screen_to_world_pos(inputs.mouse_pos))
-                                        },
-                                        Pen::None=>{ false }
-                                    };
-
-                                    if update {
-                                        temp_map.clear();
-                                        temp_map.set(sim_params.temp.plant_spawner_temp, plant_spawners.instances());
-                                        temp_map.set(sim_params.temp.fruit_spawner_temp, fruit_spawners.instances());
-                                        temp_map.update(sim_params.temp.spread,sim_params.temp.smooth,&rocks.rocks);
-                                    }
+                                    world.paint(camera.screen_to_world_pos(inputs.mouse_pos), false);
                                 }
                             }
 
@@ -347,13 +271,13 @@ pub async fn run() {
                                 }
                             }*/
 
-                            let circles = [highlighter.instances().as_slice(),fruit.instances().as_slice(),eggs.instances().as_slice(),plants.instances().as_slice()].concat();
-                            let squares = [temp_map.instances().as_slice(),rocks.instances().as_slice(),fruit_spawners.instances().as_slice(),plant_spawners.instances().as_slice()].concat();
-                            let triangles = animals.instances();
+                            let circles = [highlighter.instances().as_slice(),world.fruit().instances().as_slice(),world.eggs().instances().as_slice(),world.plants().instances().as_slice()].concat();
+                            let squares = [world.temp_map().instances().as_slice(),world.rocks().instances().as_slice(),world.fruit_spawners().instances().as_slice(),world.plant_spawners().instances().as_slice()].concat();
+                            let triangles = world.animals().instances();
 
                             renderer.update(circles,squares,triangles,camera);
 
-                            match renderer.render(&mut stats,&mut sim_params,&inspected_animal,&mut state,&mut highlighter) {
+                            match renderer.render(&mut world,&mut timeline,&inspected_animal,&mut state,&mut highlighter) {
                                 Ok(_) => {}
                                 Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
                                     renderer.resize(None);
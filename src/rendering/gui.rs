@@ -1,6 +1,7 @@
-use egui::{Align2, Color32, Context, emath, Frame, RichText, Sense, Stroke, Ui, Vec2, Visuals};
+use std::path::PathBuf;
+use egui::{Align2, Color32, Context, emath, FontId, Frame, RichText, Sense, Stroke, Ui, Vec2, Visuals};
 use egui::epaint::Shadow;
-use egui_plot::{Bar, BarChart, Line, Plot, PlotPoints};
+use egui_plot::{Bar, BarChart, Legend, Line, Plot, PlotPoints, Polygon};
 use egui_wgpu::{Renderer, ScreenDescriptor};
 use egui_winit::State;
 use epaint::Pos2;
@@ -9,12 +10,19 @@ use wgpu::{CommandEncoder, Device, Queue, TextureFormat, TextureView};
 use winit::dpi::PhysicalSize;
 use winit::event::WindowEvent;
 use winit::window::Window;
-use crate::environment::animal::Animal;
+use crate::environment::animal::{Animal, DeathCause};
+use crate::environment::energy::{FeedingLink, FlowKind, Food};
+use crate::environment::species::SpeciesList;
+use crate::environment::genome::{Genome, GENES};
+use crate::environment::neural_network::{ActivationFunction, Network};
+use crate::utilities::statistics::AGE_BIN_MINUTES;
 use crate::utilities::highlighter::{Condition, Highlighter, SelectedHighlight};
-use crate::utilities::save_system::SaveSystem;
+use crate::utilities::export::export_stats;
+use crate::utilities::save_system::{SaveEntry, SaveSystem};
+use crate::utilities::timeline::Timeline;
 use crate::utilities::simulation_parameters::{Pen, SimParams};
-use crate::utilities::state::State::{CreateSim, Exit, LoadSave, Menu, NewSim, SaveSim};
-use crate::utilities::statistics::Stats;
+use crate::utilities::state::State::{CreateSim, Exit, LoadSave, Menu, NewSim, ReplaySave, ResumeAutosave, SaveSim};
+use crate::world::World;
 
 #[derive(Default)]
 pub struct Toggles{
@@ -25,11 +33,53 @@ pub struct Toggles{
     build_settings: bool,
     diagnostics: bool,
     distributions: bool,
+    life_history: bool,
+    energy_flow: bool,
     animal_inspect: bool,
     highlighter_settings: bool,
     populations: Populations,
     highlight_selected: bool,
     temperature_settings: bool,
+    phylogeny: bool,
+    selected_species: usize,
+    pinned_animal: Option<usize>,
+    export_status: Option<String>,
+    //where Export Stats writes to, the stats folder of the output directory
+    stats_dir: PathBuf,
+    timeline: bool,
+    //the snapshot selected on the timeline
+    snapshot: usize,
+    load_menu: LoadMenu,
+}
+#[derive(Default)]
+pub struct LoadMenu{
+    filter: String,
+    sort: SaveSort,
+    ascending: bool,
+    //the file of the save being renamed and the name typed so far
+    renaming: Option<(String, String)>,
+}
+#[derive(Default,Clone,Copy,PartialEq)]
+enum SaveSort{
+    #[default]
+    Created,
+    Name,
+    Step,
+    Population,
+    Species,
+}
+impl SaveSort{
+    const ALL: [SaveSort;5] = [SaveSort::Created, SaveSort::Name, SaveSort::Step, SaveSort::Population, SaveSort::Species];
+
+    fn name(&self) -> &'static str{
+        match self {
+            SaveSort::Created => "Created",
+            SaveSort::Name => "Name",
+            SaveSort::Step => "Step",
+            SaveSort::Population => "Population",
+            SaveSort::Species => "Species",
+        }
+    }
 }
 #[derive(Default)]
 pub struct Populations{
@@ -39,6 +89,7 @@ pub struct Populations{
     carnivores: bool,
     speed: bool,
     size: bool,
+    gene: usize,
 }
 pub struct EguiRenderer {
     pub context: Context,
@@ -54,6 +105,7 @@ impl EguiRenderer {
         output_depth_format: Option<TextureFormat>,
         msaa_samples: u32,
         window: &Window,
+        stats_dir: PathBuf,
     ) -> EguiRenderer {
         let egui_context = Context::default();
         let id = egui_context.viewport_id();
@@ -74,6 +126,7 @@ impl EguiRenderer {
 
         let mut toggles = Toggles::default();
         toggles.populations.animals = true;
+        toggles.stats_dir = stats_dir;
 
         EguiRenderer {
             context: egui_context,
@@ -95,9 +148,9 @@ impl EguiRenderer {
         window: &Window,
         window_surface_view: &TextureView,
         screen_descriptor: ScreenDescriptor,
-        run_ui: impl FnOnce(&Context,&mut Stats,&mut Toggles,&mut SimParams,&Option<Animal>,&mut crate::utilities::state::State, &mut Highlighter),
-        stats: &mut Stats,
-        sim_params: &mut SimParams,
+        run_ui: impl FnOnce(&Context,&mut World,&mut Timeline,&mut Toggles,&Option<Animal>,&mut crate::utilities::state::State, &mut Highlighter),
+        world: &mut World,
+        timeline: &mut Timeline,
         animal: &Option<Animal>,
         state: &mut crate::utilities::state::State,
         highlighter: &mut Highlighter
@@ -105,7 +158,7 @@ impl EguiRenderer {
         let raw_input = self.state.take_egui_input(window);
 
         let full_output = self.context.run(raw_input, |_ui| {
-            run_ui(&self.context,stats,&mut self.toggles,sim_params,animal,state,highlighter);
+            run_ui(&self.context,world,timeline,&mut self.toggles,animal,state,highlighter);
         });
 
         self.state
@@ -148,7 +201,7 @@ impl EguiRenderer {
         window: &Window,
         window_surface_view: &TextureView,
         screen_descriptor: ScreenDescriptor,
-        run_ui: impl FnOnce(&Context,&mut crate::utilities::state::State,&mut SimParams,&mut SaveSystem,&PhysicalSize<u32>),
+        run_ui: impl FnOnce(&Context,&mut crate::utilities::state::State,&mut SimParams,&mut SaveSystem,&mut Toggles,&PhysicalSize<u32>),
         state: &mut crate::utilities::state::State,
         sim_params: &mut SimParams,
         save_system: &mut SaveSystem,
@@ -156,7 +209,7 @@ impl EguiRenderer {
     ) {
         let raw_input = self.state.take_egui_input(window);
         let full_output = self.context.run(raw_input, |_ui| {
-            run_ui(&self.context,state,sim_params,save_system,screen_size);
+            run_ui(&self.context,state,sim_params,save_system,&mut self.toggles,screen_size);
         });
 
         self.state
@@ -193,11 +246,12 @@ impl EguiRenderer {
     }
 }
 
-pub fn gui(ui: &Context,stats: &mut Stats,toggles: &mut Toggles,sim_params: &mut SimParams,animal: &Option<Animal>, state: &mut crate::utilities::state::State,highlighter: &mut Highlighter) {
+pub fn gui(ui: &Context,world: &mut World,timeline: &mut Timeline,toggles: &mut Toggles,animal: &Option<Animal>, state: &mut crate::utilities::state::State,highlighter: &mut Highlighter) {
     egui::SidePanel::right("right")
         .resizable(false)
         .default_width(200.)
         .show(ui,|ui|{
+            let sim_params = world.sim_params_mut();
             ui.heading("Statistics");
             ui.separator();
 
@@ -210,9 +264,21 @@ pub fn gui(ui: &Context,stats: &mut Stats,toggles: &mut Toggles,sim_params: &mut
             if ui.selectable_label(toggles.distributions, RichText::new("Distributions").heading()).clicked(){
                 toggles.distributions = !toggles.distributions;
             }
+            if ui.selectable_label(toggles.life_history, RichText::new("Life History").heading()).clicked(){
+                toggles.life_history = !toggles.life_history;
+            }
+            if ui.selectable_label(toggles.energy_flow, RichText::new("Energy Flow").heading()).clicked(){
+                toggles.energy_flow = !toggles.energy_flow;
+            }
+            if ui.selectable_label(toggles.phylogeny, RichText::new("Phylogeny").heading()).clicked(){
+                toggles.phylogeny = !toggles.phylogeny;
+            }
             if ui.selectable_label(toggles.diagnostics, RichText::new("Diagnostics").heading()).clicked(){
                 toggles.diagnostics = !toggles.diagnostics;
             }
+            if ui.selectable_label(toggles.timeline, RichText::new("Timeline").heading()).clicked(){
+                toggles.timeline = !toggles.timeline;
+            }
 
             ui.separator();
             ui.heading("Settings");
@@ -244,19 +310,79 @@ pub fn gui(ui: &Context,stats: &mut Stats,toggles: &mut Toggles,sim_params: &mut
                 ui.label(RichText::new("Autosave").heading());
                 ui.add(egui::DragValue::new(&mut sim_params.autosave).clamp_range(60..=6000).speed(10).suffix(" min"));
             });
+            ui.horizontal(|ui| {
+                ui.label(RichText::new("Keep").heading());
+                ui.add(egui::DragValue::new(&mut sim_params.autosave_keep).clamp_range(1..=100).suffix(" autosaves"));
+            });
+            ui.add_sized([180.,20.], egui::TextEdit::singleline(world.name_mut()).hint_text("Save name"));
             if ui.add_sized([180.,30.],egui::Button::new(RichText::new("Save").heading())).clicked(){
                 *state = SaveSim;
             }
+            if ui.add_sized([180.,30.],egui::Button::new(RichText::new("Export Stats").heading())).clicked(){
+                let dir = &toggles.stats_dir;
+                toggles.export_status = Some(match export_stats(world.stats(), dir, world.current_step()) {
+                    Ok(()) => format!("Exported to {}", dir.display()),
+                    Err(err) => format!("Export failed: {}", err),
+                });
+            }
+            if let Some(status) = &toggles.export_status {
+                ui.label(RichText::new(status));
+            }
+            if let Some(replay) = world.replay_status() {
+                match &replay.divergence {
+                    Some(divergence) => ui.label(RichText::new(["Replay", &divergence.to_string()].join(" ")).color(Color32::LIGHT_RED)),
+                    None if replay.finished(world.current_step()) => ui.label(RichText::new(format!("Replay matched up to step {}", replay.end_step)).color(Color32::LIGHT_GREEN)),
+                    None => ui.label(format!("Replaying to step {}", replay.end_step)),
+                };
+            }
             if ui.add_sized([180.,30.],egui::Button::new(RichText::new("Main Menu").heading())).clicked(){
                 *state = Menu;
             }
         });
 
+    if toggles.timeline{
+        egui::Window::new("Timeline")
+            .resizable(false)
+            .collapsible(false)
+            .show(ui, |ui| {
+                let step = world.current_step();
+                let count = timeline.snapshots().len();
+
+                if count == 0 {
+                    ui.label("No snapshots yet");
+                }
+                else {
+                    toggles.snapshot = toggles.snapshot.min(count - 1);
+                    let steps: Vec<i32> = timeline.snapshots().iter().map(|snapshot| snapshot.step).collect();
+
+                    ui.add(egui::Slider::new(&mut toggles.snapshot, 0..=count - 1)
+                        .custom_formatter(|i, _| format!("step {}", steps[i as usize])));
+                    ui.label(format!("{:.1} minutes ago", (step - steps[toggles.snapshot]) as f32 / 3600.));
+
+                    if ui.add_sized([180.,30.],egui::Button::new(RichText::new("Restore").heading())).clicked(){
+                        match timeline.restore(toggles.snapshot, world) {
+                            Ok(()) => highlighter.set_highlights(world.animals()),
+                            Err(err) => toggles.export_status = Some(format!("Restore failed: {}", err)),
+                        }
+                    }
+                }
+
+                ui.separator();
+                let mut interval = timeline.interval() / 60;
+                if ui.add(egui::DragValue::new(&mut interval).clamp_range(1..=600).prefix("Every ").suffix(" s")).changed(){
+                    timeline.set_interval(interval * 60);
+                }
+                ui.add(egui::DragValue::new(&mut timeline.capacity).clamp_range(1..=500).prefix("Keep ").suffix(" snapshots"));
+                ui.label(format!("{:.1} MB in memory", timeline.size() as f32 / 1_000_000.));
+            });
+    }
+
     if toggles.temperature_settings{
         egui::Window::new("Temperature settings")
             .resizable(false)
             .collapsible(false)
             .show(ui, |ui| {
+                let sim_params = world.sim_params_mut();
                 ui.horizontal(|ui|{
                     ui.label("Spread");
                     ui.add(egui::DragValue::new(&mut sim_params.temp.spread).clamp_range(0.1..=0.99).speed(0.01));
@@ -294,61 +420,17 @@ pub fn gui(ui: &Context,stats: &mut Stats,toggles: &mut Toggles,sim_params: &mut
                         ui.separator();
                         ui.label("Internal");
                     });
-                    Frame::canvas(ui.style()).show(ui, |ui| {
-                        let (response, painter) = ui.allocate_painter(Vec2::new(ui.available_width(), ui.available_width() * 0.5), Sense::hover());
-
-                        let to_screen = emath::RectTransform::from_to(
-                            Rect::from_min_size(epaint::Pos2::ZERO, response.rect.square_proportions()),
-                            response.rect,
-                        );
-
-                        let network = &animal.brain.network;
-
-                        let spacing_x = 0.9 / (network.layers.len() as f32 - 1.0);
-                        let neurons: Vec<Shape> = network.layers.iter().enumerate().flat_map(|(i, layer)| {
-                            let spacing = 1.9 / (layer.neurons.len() - 1) as f32;
-                            layer.neurons.iter().enumerate().map(move |(j, neuron)| {
-                                let c = (neuron.activation * 255.) as u8;
-                                let fill = Color32::from_rgb(c, c, c);
-
-                                Shape::Circle(CircleShape {
-                                    center: to_screen * Pos2::new(0.05 + j as f32 * spacing, 0.05 + i as f32 * spacing_x),
-                                    radius: 6.0,
-                                    fill,
-                                    stroke: Default::default(),
-                                })
-                            })
-                        }).collect();
-
-                        let synapses: Vec<Shape> = network.layers.iter().enumerate().flat_map(|(i, layer)| {
-                            let spacing = 1.9 / (layer.neurons.len() - 1) as f32;
-
-                            layer.neurons.iter().enumerate().flat_map(move |(j, neuron)| {
-                                let p1 = Pos2::new(0.05 + j as f32 * spacing, 0.05 + i as f32 * spacing_x);
-                                let spacing2 = 1.9 / (neuron.weights.len() as f32 - 1.);
-
-                                neuron.weights.iter().enumerate().map(move |(k, weight)| {
-                                    let color = if *weight > 0. { Color32::from_rgb(0, 255, 0) } else { Color32::from_rgb(255, 0, 0) };
-
-                                    let width = (weight * 2.0).abs().min(3.0);
-
-                                    let p2 = Pos2::new(0.05 + k as f32 * spacing2, 0.05 + (i as f32 - 1.0) * spacing_x);
-
-                                    Shape::LineSegment { points: [to_screen.transform_pos(p1), to_screen.transform_pos(p2)], stroke: Stroke{ width, color }}
-                                })
-                            })
-                        }).collect();
-
-                        painter.extend(synapses);
-                        painter.extend(neurons);
-                    });
+                    network_painter(ui, &animal.brain.network);
                     ui.horizontal(|ui| {
                         ui.label("    Move    ");
                         ui.separator();
                         ui.label("                           Turn                           ");
                         ui.separator();
-                        ui.label("                               Endocrine");
+                        ui.label("                               Endocrine                ");
+                        ui.separator();
+                        ui.label("Memory");
                     });
+                    ui.label(RichText::new(ActivationFunction::ALL.iter().map(|function| [function.symbol(), function.name()].join(" ")).collect::<Vec<_>>().join("   ")).small());
 
                     ui.separator();
 
@@ -359,46 +441,154 @@ pub fn gui(ui: &Context,stats: &mut Stats,toggles: &mut Toggles,sim_params: &mut
                             ui.label(RichText::new(format!("Maturity: {}", animal.maturity)));
                             ui.label(RichText::new(format!("Age (min): {:.2}", animal.age/60.)));
                             ui.label(RichText::new(format!("Generation: {}", animal.generation)));
+                            ui.label(RichText::new(format!("Offspring: {}", animal.offspring_count)));
                         });
                         ui.vertical(|ui|{
                             ui.label(RichText::new(format!("Energy: {:.2}", animal.resources.energy)));
                             ui.label(RichText::new(format!("Protein: {:.2}", animal.resources.protein)));
                             ui.label(RichText::new(format!("Mass: {:.2}", animal.lean_mass)));
-                            ui.label(RichText::new(format!("Hue: {:.2}", animal.hue)));
                         });
                         ui.vertical(|ui|{
-                            ui.label(RichText::new(format!("Animal vision: {:.2}", animal.senses.animal_vision)));
-                            ui.label(RichText::new(format!("Plant vision: {:.2}", animal.senses.plant_vision)));
-                            ui.label(RichText::new(format!("Fruit vision: {:.2}", animal.senses.fruit_vision)));
-                            ui.label(RichText::new(format!("Rock vision: {:.2}", animal.senses.rock_vision)));
-                        });
-                        ui.vertical(|ui|{
-                            ui.label(RichText::new(format!("Offspring invest: {:.2}", animal.reproduction_stats.offspring_investment)));
                             ui.label(RichText::new(format!("Birth recovery (s): {:.2}", animal.reproduction_stats.birth_timer)));
                             ui.label(RichText::new(format!("Temperature: {:.2}", animal.temperature)));
-                            ui.label(RichText::new(format!("Ideal temp: {:.2}", animal.ideal_temp)));
-                            ui.label(RichText::new(format!("Temp tolerance: {:.2}", animal.temp_tolerance)));
                         });
                         ui.vertical(|ui|{
                             ui.label(RichText::new(format!("Speed: {:.2}", animal.combat_stats.speed)));
                             ui.label(RichText::new(format!("Size: {:.2}", animal.body.scale)));
-                            ui.label(RichText::new(format!("Carnivore factor: {:.2}", animal.combat_stats.carnivore_factor)));
                             ui.label(RichText::new(format!("Attack: {:.2}", animal.combat_stats.attack)));
                             ui.label(RichText::new(format!("Aggression: {:.2}", animal.combat_stats.aggression)));
                         });
                     });
+
+                    ui.separator();
+                    ui.label(RichText::new("Genome").strong());
+
+                    genome_labels(ui, &animal.genome);
+
+                    ui.separator();
+                    ui.label(RichText::new("Lineage").strong());
+
+                    let lineage = world.animals().lineage();
+                    let line = lineage.line_of_descent(animal.id);
+                    let mut descent = line.iter().take(12).map(|id| format!("#{}", id)).collect::<Vec<_>>().join(" <- ");
+                    if line.len() > 12 {
+                        descent.push_str(" <- ...");
+                    }
+                    if let [Some(parent), Some(mate)] = lineage.parents(animal.id) {
+                        ui.label(RichText::new(format!("Parents: #{} and #{}", parent, mate)));
+                    }
+                    ui.label(RichText::new(format!("Line of descent: {}", descent)));
+
+                    ui.horizontal(|ui|{
+                        if ui.button("Pin for comparison").clicked(){
+                            toggles.pinned_animal = Some(animal.id);
+                        }
+                        if let Some(pinned) = toggles.pinned_animal.filter(|pinned| *pinned != animal.id) {
+                            match lineage.common_ancestor(animal.id, pinned) {
+                                Some((ancestor, back, back_pinned)) => {
+                                    ui.label(RichText::new(format!("Common ancestor with #{}: #{} ({} and {} generations back)", pinned, ancestor, back, back_pinned)));
+                                }
+                                None => { ui.label(RichText::new(format!("No common ancestor with #{}", pinned))); }
+                            }
+                        }
+                    });
                 }
                 else{
                     ui.label(RichText::new("No animal selected"));
                 }
             });
     }
+    if toggles.phylogeny {
+        egui::Window::new("Phylogeny")
+            .default_width(550.0)
+            .resizable(true)
+            .collapsible(false)
+            .show(ui, |ui| {
+                let step = world.current_step().max(1);
+                let species_list = world.species_list();
+                let order = species_list.depth_first();
+                let mut rows = vec![0; species_list.count() + 1];
+                order.iter().enumerate().for_each(|(row, id)| rows[*id] = row);
+
+                let mut clicked = None;
+
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    Frame::canvas(ui.style()).show(ui, |ui| {
+                        let row_height = 10.0;
+                        let (response, painter) = ui.allocate_painter(Vec2::new(ui.available_width(), order.len() as f32 * row_height + 16.0), Sense::click());
+                        let rect = response.rect;
+
+                        let x = |step_at: i32| rect.left() + 10.0 + (rect.width() - 20.0) * step_at as f32 / step as f32;
+                        let y = |id: usize| rect.top() + 8.0 + rows[id] as f32 * row_height;
+
+                        order.iter().for_each(|id| {
+                            let species = species_list.get(*id).unwrap();
+                            let end = species.extinction_step.unwrap_or(step);
+
+                            let color = if *id == toggles.selected_species { Color32::WHITE }
+                                else if species.extinction_step.is_some() { Color32::DARK_GRAY }
+                                else { egui::ecolor::Hsva::new(species.specimen.hue, 0.8, 0.9, 1.0).into() };
+                            let width = 1.0 + (species.population as f32).sqrt();
+
+                            if species.parent > 0 {
+                                painter.line_segment([Pos2::new(x(species.birth_step), y(species.parent)), Pos2::new(x(species.birth_step), y(*id))], Stroke::new(1.0, Color32::GRAY));
+                            }
+                            painter.line_segment([Pos2::new(x(species.birth_step), y(*id)), Pos2::new(x(end), y(*id))], Stroke::new(width, color));
+                        });
+
+                        if response.clicked() {
+                            if let Some(pos) = response.interact_pointer_pos() {
+                                let row = ((pos.y - rect.top() - 8.0) / row_height).round().max(0.0) as usize;
+                                clicked = order.get(row).copied();
+                            }
+                        }
+                    });
+                });
+
+                if let Some(id) = clicked {
+                    toggles.selected_species = id;
+                    highlighter.selected_highlight = SelectedHighlight::Species;
+                    highlighter.species_id = id;
+                    highlighter.set_highlights(world.animals());
+                }
+
+                ui.separator();
+
+                if let Some(species) = world.species_list().get(toggles.selected_species) {
+                    ui.horizontal(|ui|{
+                        ui.vertical(|ui|{
+                            ui.label(RichText::new(format!("Species: {}", toggles.selected_species)));
+                            ui.label(RichText::new(format!("Parent: {}", species.parent)));
+                            ui.label(RichText::new(format!("Population: {}", species.population)));
+                        });
+                        ui.vertical(|ui|{
+                            ui.label(RichText::new(format!("Appeared (min): {:.2}", species.birth_step as f32 / 3600.)));
+                            match species.extinction_step {
+                                Some(extinction_step) => ui.label(RichText::new(format!("Extinct (min): {:.2}", extinction_step as f32 / 3600.))),
+                                None => ui.label(RichText::new("Extant")),
+                            };
+                            ui.label(RichText::new(format!("Total born: {}", species.count)));
+                        });
+                    });
+
+                    ui.separator();
+                    ui.label(RichText::new("Specimen").strong());
+                    genome_labels(ui, &species.specimen.genome);
+                    network_painter(ui, &species.specimen.brain.network);
+                }
+                else{
+                    ui.label(RichText::new("Click a species to select it"));
+                }
+            });
+    }
     if toggles.population_graphs {
         egui::Window::new("Population graphs")
             .default_width(550.0)
             .resizable(true)
             .collapsible(false)
             .show(ui, |ui| {
+                let stats = world.stats();
+                let mut clear = false;
                 ui.collapsing(RichText::new("Animals"),|ui|{
                     let animals =Line::new(PlotPoints::new(stats.populations.animals.clone())).color(Color32::WHITE);
 
@@ -439,6 +629,89 @@ pub fn gui(ui: &Context,stats: &mut Stats,toggles: &mut Toggles,sim_params: &mut
                     });
                 });
 
+                ui.collapsing(RichText::new("Genes"),|ui|{
+                    egui::ComboBox::from_label("Gene")
+                        .selected_text(GENES[toggles.populations.gene].name)
+                        .show_ui(ui, |ui| {
+                            GENES.iter().enumerate().for_each(|(i, gene)|{
+                                ui.selectable_value(&mut toggles.populations.gene, i, gene.name);
+                            });
+                        });
+
+                    let points = stats.populations.average_genes.get(toggles.populations.gene).cloned().unwrap_or_default();
+                    let gene = Line::new(PlotPoints::new(points)).color(Color32::LIGHT_BLUE);
+
+                    Plot::new("genes graph").view_aspect(2.0).show(ui, |plot_ui| {
+                        plot_ui.line(gene);
+                    });
+                });
+
+                ui.collapsing(RichText::new("Diversity"),|ui|{
+                    let diversity = &stats.populations.diversity;
+                    let richness = Line::new(PlotPoints::new(diversity.richness.clone())).color(Color32::WHITE).name("Living species");
+
+                    Plot::new("richness graph").view_aspect(3.0).legend(Legend::default()).show(ui, |plot_ui| {
+                        plot_ui.line(richness);
+                    });
+
+                    let shannon = Line::new(PlotPoints::new(diversity.shannon.clone())).color(Color32::LIGHT_BLUE).name("Shannon");
+                    let simpson = Line::new(PlotPoints::new(diversity.simpson.clone())).color(Color32::GOLD).name("Simpson");
+                    let disparity = Line::new(PlotPoints::new(diversity.disparity.clone())).color(Color32::from_rgb(255,120,200)).name("Disparity");
+
+                    Plot::new("diversity graph").view_aspect(2.0).legend(Legend::default()).show(ui, |plot_ui| {
+                        plot_ui.line(shannon);
+                        plot_ui.line(simpson);
+                        plot_ui.line(disparity);
+                    });
+                });
+
+                ui.collapsing(RichText::new("Species"),|ui|{
+                    let species_list = world.species_list();
+
+                    //founders that haven't speciated yet sit at the bottom, then species in tree order so daughters are stacked beside their parents
+                    let order: Vec<usize> = [0].into_iter().chain(species_list.depth_first()).filter(|id| stats.populations.species.contains_key(id)).collect();
+                    let color = |id: usize| match species_list.get(id) {
+                        Some(species) => egui::ecolor::Hsva::new(species.specimen.hue, 0.8, 0.9, 1.0).into(),
+                        None => Color32::GRAY,
+                    };
+
+                    //at most 200 columns so that long runs stay cheap to draw
+                    let samples = &stats.populations.animals;
+                    let stride = (samples.len() / 200).max(1);
+                    let times: Vec<f64> = samples.iter().step_by(stride).map(|sample| sample[0]).collect();
+
+                    let mut lower = vec![0.; times.len()];
+                    let bands: Vec<(usize, Vec<f64>, Vec<f64>)> = order.iter().map(|id| {
+                        let population = &stats.populations.species[id].population;
+                        let upper: Vec<f64> = times.iter().zip(lower.iter()).map(|(time, lower)| {
+                            lower + population.binary_search_by(|sample| sample[0].total_cmp(time)).map(|i| population[i][1]).unwrap_or(0.)
+                        }).collect();
+                        let band = (*id, lower.clone(), upper.clone());
+                        lower = upper;
+                        band
+                    }).collect();
+
+                    Plot::new("muller plot").view_aspect(2.0).show(ui, |plot_ui| {
+                        bands.iter().for_each(|(id, lower, upper)| {
+                            for i in 1..times.len() {
+                                if upper[i-1] > lower[i-1] || upper[i] > lower[i] {
+                                    let quad = vec![[times[i-1], lower[i-1]], [times[i], lower[i]], [times[i], upper[i]], [times[i-1], upper[i-1]]];
+                                    plot_ui.polygon(Polygon::new(PlotPoints::new(quad)).fill_color(color(*id)).stroke(Stroke::NONE));
+                                }
+                            }
+                        });
+                    });
+
+                    ui.label(RichText::new(format!("Mean {} of species {}", GENES[toggles.populations.gene].name.to_lowercase(), toggles.selected_species)));
+                    let points: Vec<[f64;2]> = stats.populations.species.get(&toggles.selected_species)
+                        .map(|series| series.population.iter().zip(series.mean_genes.iter()).filter(|(sample, _)| sample[1] > 0.).map(|(sample, genes)| [sample[0], genes[toggles.populations.gene] as f64]).collect())
+                        .unwrap_or_default();
+
+                    Plot::new("species genes graph").view_aspect(2.0).show(ui, |plot_ui| {
+                        plot_ui.line(Line::new(PlotPoints::new(points)).color(color(toggles.selected_species)));
+                    });
+                });
+
                 ui.collapsing(RichText::new("Food"),|ui|{
                     let plants =Line::new(PlotPoints::new(stats.populations.plants.clone())).color(Color32::GREEN);
                     let fruit =Line::new(PlotPoints::new(stats.populations.fruit.clone())).color(Color32::LIGHT_GREEN);
@@ -452,7 +725,11 @@ pub fn gui(ui: &Context,stats: &mut Stats,toggles: &mut Toggles,sim_params: &mut
                 ui.separator();
 
                 if ui.selectable_label(false, RichText::new("Clear graphs")).clicked(){
-                    stats.clear_graph_data();
+                    clear = true;
+                }
+
+                if clear {
+                    world.stats_mut().clear_graph_data();
                 }
             });
     }
@@ -461,6 +738,7 @@ pub fn gui(ui: &Context,stats: &mut Stats,toggles: &mut Toggles,sim_params: &mut
             .resizable(false)
             .collapsible(false)
             .show(ui, |ui| {
+                let stats = world.stats_mut();
                 ui.label(RichText::new(format!("FPS: {}",stats.fps)));
                 ui.label(RichText::new(format!("Total CPU usage: {:.2}%",stats.tot_cpu_usage)));
                 ui.label(RichText::new(format!("Total memory: {} mB",stats.tot_mem/8000000)));
@@ -476,42 +754,119 @@ pub fn gui(ui: &Context,stats: &mut Stats,toggles: &mut Toggles,sim_params: &mut
         egui::Window::new("Distributions")
             .default_width(550.0)
             .collapsible(false)
+            .vscroll(true)
             .show(ui, |ui| {
-                ui.collapsing(RichText::new("Diet"),|ui|{
-                    let bars = stats.distributions.diet.iter().enumerate().map(|(i,diet)|{
-                        Bar::new(i as f64 * 0.1, *diet).fill(Color32::from_rgba_unmultiplied(i as u8 * 25,255 - i as u8 * 25,25,80)).width(0.08)
-                            .stroke(Stroke::new(1., Color32::from_rgb(i as u8 * 25,255 - i as u8 * 25,25)))
-                    }).collect();
-                    let chart = BarChart::new(bars);
+                let distributions = &world.stats().distributions;
+                let colours = [
+                    Color32::from_rgb(25,255,25), Color32::from_rgb(25,80,255), Color32::from_rgb(255,200,25), Color32::from_rgb(255,40,40),
+                    Color32::from_rgb(255,120,200), Color32::from_rgb(120,255,120), Color32::from_rgb(255,160,60), Color32::from_rgb(160,160,160),
+                    Color32::from_rgb(255,90,25), Color32::from_rgb(25,220,220), Color32::from_rgb(200,120,255), Color32::from_rgb(240,240,240),
+                ];
+
+                distributions.histograms().iter().zip(colours).enumerate().for_each(|(i, (histogram, colour))|{
+                    ui.collapsing(RichText::new(histogram.name),|ui|{
+                        let bars = histogram.counts.iter().enumerate().map(|(j,count)|{
+                            Bar::new(histogram.start + j as f64 * histogram.width, *count).fill(colour.gamma_multiply(0.3)).width(histogram.width * 0.8)
+                                .stroke(Stroke::new(1., colour))
+                        }).collect();
+                        let chart = BarChart::new(bars);
 
-                    Plot::new("Diet").view_aspect(2.0).show(ui, |plot_ui| {
-                        plot_ui.bar_chart(chart);
+                        Plot::new(histogram.name).view_aspect(2.0).show(ui, |plot_ui| {
+                            plot_ui.bar_chart(chart);
+                        });
+
+                        ui.label(RichText::new("Over time, brighter cells hold a larger share of the population"));
+                        histogram_heatmap(ui, &distributions.history, i, colour);
                     });
                 });
-                ui.collapsing(RichText::new("Speed"),|ui|{
-                    let bars = stats.distributions.speed.iter().enumerate().map(|(i,speed)|{
-                        Bar::new(i as f64 * 0.1, *speed).fill(Color32::from_rgba_unmultiplied(i as u8 * 15,i as u8 * 25,255,80)).width(0.08)
-                        .stroke(Stroke::new(1., Color32::from_rgb(i as u8 * 15,i as u8 * 25,255)))
+            });
+    }
+    if toggles.life_history {
+        egui::Window::new("Life history")
+            .default_width(550.0)
+            .collapsible(false)
+            .show(ui, |ui| {
+                let life_history = &world.stats().life_history;
+                let colours = [Color32::RED, Color32::GOLD, Color32::LIGHT_BLUE, Color32::GRAY];
+
+                DeathCause::ALL.iter().enumerate().for_each(|(i, cause)|{
+                    ui.label(RichText::new(format!("{} deaths: {}", cause.name(), life_history.total_deaths.get(i).copied().unwrap_or(0))));
+                });
+                ui.label(RichText::new(format!("Mean age at death: {:.1} minutes", life_history.mean_age_at_death())));
+
+                ui.collapsing(RichText::new("Deaths by cause"),|ui|{
+                    Plot::new("deaths by cause graph").view_aspect(2.0).legend(Legend::default()).show(ui, |plot_ui| {
+                        life_history.deaths.iter().zip(DeathCause::ALL).zip(colours).for_each(|((series, cause), colour)|{
+                            plot_ui.line(Line::new(PlotPoints::new(series.clone())).color(colour).name(cause.name()));
+                        });
+                    });
+                });
+                ui.collapsing(RichText::new("Age at death"),|ui|{
+                    let bars = life_history.age_at_death.iter().enumerate().map(|(i,count)|{
+                        Bar::new((i as f32 * AGE_BIN_MINUTES) as f64, *count).fill(Color32::from_rgba_unmultiplied(180,180,180,80)).width(AGE_BIN_MINUTES as f64 * 0.8)
+                            .stroke(Stroke::new(1., Color32::from_rgb(180,180,180)))
                     }).collect();
                     let chart = BarChart::new(bars);
 
-                    Plot::new("Speed").view_aspect(2.0).show(ui, |plot_ui| {
+                    Plot::new("Age at death").view_aspect(2.0).show(ui, |plot_ui| {
                         plot_ui.bar_chart(chart);
                     });
                 });
-                ui.collapsing(RichText::new("Size"),|ui|{
-                    let bars = stats.distributions.size.iter().enumerate().map(|(i,size)|{
-                        Bar::new(i as f64 * 0.1, *size).fill(Color32::from_rgba_unmultiplied(255,255-i as u8 * 18,25,80)).width(0.08)
-                        .stroke(Stroke::new(1., Color32::from_rgb(255,255-i as u8 * 18,25)))
+                ui.collapsing(RichText::new("Offspring per individual"),|ui|{
+                    let bars = life_history.offspring.iter().enumerate().map(|(i,count)|{
+                        Bar::new(i as f64, *count).fill(Color32::from_rgba_unmultiplied(25,255,120,80)).width(0.8)
+                            .stroke(Stroke::new(1., Color32::from_rgb(25,255,120)))
                     }).collect();
                     let chart = BarChart::new(bars);
 
-                    Plot::new("Size").view_aspect(2.0).show(ui, |plot_ui| {
+                    Plot::new("Offspring per individual").view_aspect(2.0).show(ui, |plot_ui| {
                         plot_ui.bar_chart(chart);
                     });
                 });
             });
     }
+    if toggles.energy_flow {
+        egui::Window::new("Energy flow")
+            .default_width(550.0)
+            .collapsible(false)
+            .vscroll(true)
+            .show(ui, |ui| {
+                let energy = &world.stats().energy;
+                let latest = |series: &Vec<[f64;2]>| series.last().map(|sample| sample[1]).unwrap_or(0.);
+
+                ui.label(RichText::new("Mean per step since the last sample").strong());
+                ui.horizontal(|ui|{
+                    ui.vertical(|ui|{
+                        FlowKind::ALL.iter().enumerate().for_each(|(i, kind)|{
+                            ui.label(RichText::new(format!("{}: {:.2} energy, {:.3} protein", kind.name(), energy.energy.get(i).map(latest).unwrap_or(0.), energy.protein.get(i).map(latest).unwrap_or(0.))));
+                        });
+                    });
+                    ui.vertical(|ui|{
+                        ui.label(RichText::new(format!("Energy held by animals: {:.0}", latest(&energy.stock))));
+                        ui.label(RichText::new(format!("Unaccounted energy: {:.4}", latest(&energy.imbalance))));
+                    });
+                });
+
+                ui.collapsing(RichText::new("Flows over time"),|ui|{
+                    let colours = [
+                        Color32::from_rgb(25,255,25), Color32::from_rgb(255,120,200), Color32::from_rgb(120,255,120), Color32::from_rgb(255,40,40),
+                        Color32::from_rgb(160,60,60), Color32::from_rgb(255,200,25), Color32::from_rgb(25,220,220), Color32::from_rgb(200,120,255),
+                        Color32::from_rgb(240,240,240), Color32::from_rgb(160,160,160), Color32::from_rgb(25,80,255),
+                    ];
+                    Plot::new("energy flow graph").view_aspect(2.0).legend(Legend::default()).show(ui, |plot_ui| {
+                        energy.energy.iter().zip(FlowKind::ALL).zip(colours).for_each(|((series, kind), colour)|{
+                            plot_ui.line(Line::new(PlotPoints::new(series.clone())).color(colour).name(kind.name()));
+                        });
+                        plot_ui.line(Line::new(PlotPoints::new(energy.imbalance.clone())).color(Color32::DARK_GRAY).name("Unaccounted"));
+                    });
+                });
+
+                ui.collapsing(RichText::new("Food web"),|ui|{
+                    ui.label(RichText::new("Species are raised by their trophic level, lines are as thick as the energy that flowed along them"));
+                    food_web_painter(ui, &energy.food_web, world.species_list());
+                });
+            });
+    }
     if toggles.simulation_settings {
         egui::Window::new("Simulation settings")
             .resizable(false)
@@ -519,11 +874,11 @@ pub fn gui(ui: &Context,stats: &mut Stats,toggles: &mut Toggles,sim_params: &mut
             .show(ui, |ui| {
                 ui.horizontal(|ui|{
                     ui.label("Speed");
-                    ui.add(egui::DragValue::new(&mut sim_params.simulation.steps_per_frame).clamp_range(0..=200));
+                    ui.add(egui::DragValue::new(&mut world.sim_params_mut().simulation.steps_per_frame).clamp_range(0..=200));
                 });
                 ui.horizontal(|ui| {
                     ui.label("Stats refresh time");
-                    ui.add(egui::DragValue::new(&mut stats.step_time).clamp_range(1..=600));
+                    ui.add(egui::DragValue::new(&mut world.stats_mut().step_time).clamp_range(1..=600));
                 });
             });
     }
@@ -533,6 +888,7 @@ pub fn gui(ui: &Context,stats: &mut Stats,toggles: &mut Toggles,sim_params: &mut
             .default_width(0.0)
             .collapsible(false)
             .show(ui, |ui| {
+                let sim_params = world.sim_params_mut();
                 ui.heading("Plants");
                 ui.separator();
                 ui.horizontal(|ui| {
@@ -588,6 +944,7 @@ pub fn gui(ui: &Context,stats: &mut Stats,toggles: &mut Toggles,sim_params: &mut
             .collapsible(false)
             .default_width(0.0)
             .show(ui, |ui| {
+                let sim_params = world.sim_params_mut();
                 ui.heading("Mutations");
                 ui.separator();
 
@@ -599,6 +956,10 @@ pub fn gui(ui: &Context,stats: &mut Stats,toggles: &mut Toggles,sim_params: &mut
                     ui.label("Brain mutation strength");
                     ui.add(egui::DragValue::new(&mut sim_params.animals.brain_mutation_strength).clamp_range(0..=100));
                 });
+                ui.horizontal(|ui|{
+                    ui.label("Brain structure mutation rate");
+                    ui.add(egui::DragValue::new(&mut sim_params.animals.structural_mutation_rate).clamp_range(0..=100));
+                });
                 ui.horizontal(|ui|{
                     ui.label("Physical mutation rate");
                     ui.add(egui::DragValue::new(&mut sim_params.animals.physical_mutation_rate).clamp_range(0..=100));
@@ -680,6 +1041,7 @@ pub fn gui(ui: &Context,stats: &mut Stats,toggles: &mut Toggles,sim_params: &mut
                 ui.heading("Reproduction");
                 ui.separator();
 
+                ui.checkbox(&mut sim_params.animals.sexual_reproduction, "Sexual reproduction");
                 ui.horizontal(|ui|{
                     ui.label("Reproduction energy cost");
                     ui.add(egui::DragValue::new(&mut sim_params.animals.reproduction_energy_cost).clamp_range(0.0..=5.0).speed(0.01).max_decimals(2));
@@ -713,6 +1075,7 @@ pub fn gui(ui: &Context,stats: &mut Stats,toggles: &mut Toggles,sim_params: &mut
             .collapsible(false)
             .default_width(0.0)
             .show(ui, |ui| {
+                let sim_params = world.sim_params_mut();
                 egui::ComboBox::from_label("Pen")
                     .selected_text(format!("{:?}", &sim_params.build.pen))
                     .show_ui(ui, |ui| {
@@ -771,6 +1134,153 @@ pub fn gui(ui: &Context,stats: &mut Stats,toggles: &mut Toggles,sim_params: &mut
             });
     }
 }
+fn genome_labels(ui: &mut Ui, genome: &Genome){
+    ui.horizontal(|ui|{
+        let genes: Vec<(&str, f32)> = genome.iter().map(|(gene, value)| (gene.name, value)).collect();
+        genes.chunks(4).for_each(|column|{
+            ui.vertical(|ui|{
+                column.iter().for_each(|(name, value)|{
+                    ui.label(RichText::new(format!("{}: {:.2}", name, value)));
+                });
+            });
+        });
+    });
+}
+fn network_painter(ui: &mut Ui, network: &Network){
+    Frame::canvas(ui.style()).show(ui, |ui| {
+        let (response, painter) = ui.allocate_painter(Vec2::new(ui.available_width(), ui.available_width() * 0.5), Sense::hover());
+
+        let to_screen = emath::RectTransform::from_to(
+            Rect::from_min_size(epaint::Pos2::ZERO, response.rect.square_proportions()),
+            response.rect,
+        );
+
+        let spacing_x = 0.9 / (network.layers.len() as f32 - 1.0);
+        let position = |i: usize, j: usize| {
+            let count = network.layers[i].neurons.len();
+            let x = if count > 1 { 0.05 + j as f32 * 1.9 / (count - 1) as f32 } else { 1.0 };
+            Pos2::new(x, 0.05 + i as f32 * spacing_x)
+        };
+
+        let neurons: Vec<Shape> = network.layers.iter().enumerate().flat_map(|(i, layer)| {
+            layer.neurons.iter().enumerate().map(move |(j, neuron)| {
+                let c = (neuron.activation * 255.) as u8;
+                let fill = Color32::from_rgb(c, c, c);
+
+                Shape::Circle(CircleShape {
+                    center: to_screen * position(i, j),
+                    radius: 6.0,
+                    fill,
+                    stroke: Default::default(),
+                })
+            })
+        }).collect();
+
+        let synapses: Vec<Shape> = network.layers.iter().enumerate().flat_map(|(i, layer)| {
+            layer.neurons.iter().enumerate().flat_map(move |(j, neuron)| {
+                neuron.connections.iter().map(move |connection| {
+                    let weight = connection.weight;
+                    let color = if weight > 0. { Color32::from_rgb(0, 255, 0) } else { Color32::from_rgb(255, 0, 0) };
+
+                    let width = (weight * 2.0).abs().min(3.0);
+
+                    Shape::LineSegment { points: [to_screen.transform_pos(position(i, j)), to_screen.transform_pos(position(connection.from[0], connection.from[1]))], stroke: Stroke{ width, color }}
+                })
+            })
+        }).collect();
+
+        painter.extend(synapses);
+        painter.extend(neurons);
+
+        network.layers.iter().enumerate().skip(1).for_each(|(i, layer)| {
+            layer.neurons.iter().enumerate().for_each(|(j, neuron)| {
+                painter.text(to_screen * position(i, j) + Vec2::new(0.0, 8.0), Align2::CENTER_TOP, neuron.function.symbol(), FontId::proportional(9.0), Color32::GRAY);
+            });
+        });
+    });
+}
+//plants and fruit along the bottom with each species above them at its trophic level, one more than the energy weighted mean level of what it eats
+fn food_web_painter(ui: &mut Ui, links: &[FeedingLink], species_list: &SpeciesList){
+    let mut species: Vec<usize> = links.iter().flat_map(|link| match link.food {
+        Food::Species(prey) => vec![link.eater, prey],
+        _ => vec![link.eater],
+    }).collect();
+    species.sort();
+    species.dedup();
+
+    let level = |levels: &[(usize, f64)], food: Food| match food {
+        Food::Plants | Food::Fruit => 0.,
+        Food::Species(id) => levels.iter().find(|(species, _)| *species == id).map(|(_, level)| *level).unwrap_or(1.),
+    };
+    let mut levels: Vec<(usize, f64)> = species.iter().map(|id| (*id, 1.)).collect();
+    for _ in 0..20 {
+        levels = species.iter().map(|id|{
+            let diet = links.iter().filter(|link| link.eater == *id && link.energy > 0.);
+            let total = diet.clone().map(|link| link.energy).sum::<f64>();
+            let mean = diet.map(|link| link.energy * level(&levels, link.food)).sum::<f64>() / total.max(f64::EPSILON);
+            (*id, if total > 0. { 1. + mean } else { 1. })
+        }).collect();
+    }
+    let top = levels.iter().map(|(_, level)| *level).fold(1., f64::max);
+    let largest = links.iter().map(|link| link.energy).fold(f64::EPSILON, f64::max);
+
+    Frame::canvas(ui.style()).show(ui, |ui| {
+        let (response, painter) = ui.allocate_painter(Vec2::new(ui.available_width(), 250.), Sense::hover());
+        let rect = response.rect.shrink(20.);
+
+        let position = |food: Food| match food {
+            Food::Plants => Pos2::new(rect.left() + rect.width() * 0.25, rect.bottom()),
+            Food::Fruit => Pos2::new(rect.left() + rect.width() * 0.75, rect.bottom()),
+            Food::Species(id) => {
+                let column = species.iter().position(|species| *species == id).unwrap_or(0);
+                let x = rect.left() + rect.width() * (column as f32 + 0.5) / species.len() as f32;
+                Pos2::new(x, rect.bottom() - rect.height() * (level(&levels, food) / top) as f32)
+            }
+        };
+        let colour = |food: Food| match food {
+            Food::Plants => Color32::GREEN,
+            Food::Fruit => Color32::from_rgb(255,120,200),
+            Food::Species(id) => match species_list.get(id) {
+                Some(species) => egui::ecolor::Hsva::new(species.specimen.hue, 0.8, 0.9, 1.0).into(),
+                None => Color32::GRAY,
+            },
+        };
+
+        links.iter().filter(|link| link.energy > 0.).for_each(|link|{
+            let width = 1. + 5. * (link.energy / largest).sqrt() as f32;
+            painter.line_segment([position(link.food), position(Food::Species(link.eater))], Stroke::new(width, colour(link.food).gamma_multiply(0.6)));
+        });
+
+        let nodes = [(Food::Plants, "Plants".to_string()), (Food::Fruit, "Fruit".to_string())].into_iter()
+            .chain(species.iter().map(|id| (Food::Species(*id), if *id == 0 { "Founders".to_string() } else { format!("S{}", id) })));
+        nodes.for_each(|(food, name)|{
+            painter.circle_filled(position(food), 6., colour(food));
+            painter.text(position(food) + Vec2::new(0., -8.), Align2::CENTER_BOTTOM, name, FontId::proportional(10.0), Color32::WHITE);
+        });
+    });
+}
+//one column per history sample with the lowest bin at the bottom, each column scaled by its own total
+fn histogram_heatmap(ui: &mut Ui, history: &[(f64,Vec<Vec<f64>>)], histogram: usize, colour: Color32){
+    Frame::canvas(ui.style()).show(ui, |ui| {
+        let (response, painter) = ui.allocate_painter(Vec2::new(ui.available_width(), 120.), Sense::hover());
+        let rect = response.rect;
+
+        //at most 200 columns so that long runs stay cheap to draw
+        let stride = (history.len() / 200).max(1);
+        let columns: Vec<&Vec<f64>> = history.iter().step_by(stride).filter_map(|(_, histograms)| histograms.get(histogram)).collect();
+        let Some(bins) = columns.first().map(|column| column.len()) else { return };
+
+        let width = rect.width() / columns.len() as f32;
+        let height = rect.height() / bins as f32;
+        columns.iter().enumerate().for_each(|(x, column)|{
+            let total = column.iter().sum::<f64>().max(1.);
+            column.iter().enumerate().filter(|(_, count)| **count > 0.).for_each(|(y, count)|{
+                let min = Pos2::new(rect.left() + x as f32 * width, rect.bottom() - (y + 1) as f32 * height);
+                painter.rect_filled(Rect::from_min_size(min, Vec2::new(width, height)), 0., colour.gamma_multiply((count / total) as f32));
+            });
+        });
+    });
+}
 fn highlight_settings(ui: &mut Ui,con: &mut Condition){
     ui.horizontal(|ui|{
         ui.label("Bounded");
@@ -786,32 +1296,36 @@ fn highlight_settings(ui: &mut Ui,con: &mut Condition){
     });
 }
 
-pub fn main_menu_gui(ui: &Context, state: &mut crate::utilities::state::State,sim_params: &mut SimParams, save_system: &mut SaveSystem, screen_size: &PhysicalSize<u32>) {
+pub fn main_menu_gui(ui: &Context, state: &mut crate::utilities::state::State,sim_params: &mut SimParams, save_system: &mut SaveSystem, toggles: &mut Toggles, screen_size: &PhysicalSize<u32>) {
     egui::Window::new("Main Menu").default_width(0.).anchor(Align2::CENTER_CENTER, [0.,0.]).collapsible(false).resizable(false).fixed_pos([screen_size.width as f32/2. , screen_size.height as f32/2.]).show(ui, |ui|{
         match state{
             Menu =>{
                 if ui.add_sized([288., 50.], egui::Button::new(RichText::new("New Simulation").heading())).clicked(){
                     *state = CreateSim;
                 }
+                if !save_system.autosaves().is_empty() && ui.add_sized([288., 40.], egui::Button::new(RichText::new("Resume Autosave").heading())).on_hover_text("Continue from the newest autosave").clicked(){
+                    *state = ResumeAutosave;
+                }
 
                 ui.separator();
 
-                egui::ScrollArea::vertical().max_height(108.).show(ui, |ui| {
-                    for i in (0..save_system.saves.len()).rev() {
-                        ui.horizontal(|ui|{
-                            if ui.add_sized([240., 50.], egui::Button::new(RichText::new(["Load",&save_system.saves[i]].join(" ")).heading())).clicked(){
-                                sim_params.save_id = i;
-                                *state = LoadSave;
-                            }
-                            if ui.add_sized([40., 50.], egui::Button::new(RichText::new("X"))).clicked(){
-                                save_system.delete(i);
-                            }
-                        });
-                    }
-                    if save_system.saves.is_empty(){
-                        ui.label(RichText::new("No Saves").heading());
-                    }
-                });
+                load_menu(ui, state, sim_params, save_system, &mut toggles.load_menu);
+
+                ui.separator();
+
+                if let Some(err) = &save_system.last_error {
+                    ui.separator();
+                    ui.add_sized([288., 0.], egui::Label::new(RichText::new(err.to_string()).color(Color32::LIGHT_RED)).wrap(true));
+                    ui.horizontal(|ui|{
+                        if ui.button("Dismiss").clicked(){
+                            save_system.last_error = None;
+                        }
+                        if ui.button("Refresh saves").clicked(){
+                            let refreshed = save_system.refresh();
+                            save_system.last_error = refreshed.err();
+                        }
+                    });
+                }
 
                 ui.separator();
 
@@ -833,8 +1347,135 @@ pub fn main_menu_gui(ui: &Context, state: &mut crate::utilities::state::State,si
                 ui.add(egui::Checkbox::new(&mut sim_params.world.generate_terrain,"Terrain"));
                 ui.add(egui::DragValue::new(&mut sim_params.world.plant_spawners).prefix("Plant feeders: ").clamp_range(0..=200));
                 ui.add(egui::DragValue::new(&mut sim_params.world.fruit_spawners).prefix("Fruit feeders: ").clamp_range(0..=200));
+                ui.horizontal(|ui|{
+                    ui.add(egui::DragValue::new(&mut sim_params.world.seed).prefix("Seed: "));
+                    if ui.button("Randomise").clicked(){
+                        sim_params.world.seed = rand::random();
+                    }
+                });
             }
             _ => {}
         }
     });
-}
\ No newline at end of file
+}
+
+//lists the saves matching the filter in the chosen order, with their metadata when they have it
+fn load_menu(ui: &mut Ui, state: &mut crate::utilities::state::State, sim_params: &mut SimParams, save_system: &mut SaveSystem, menu: &mut LoadMenu){
+    ui.horizontal(|ui|{
+        ui.add_sized([120., 20.], egui::TextEdit::singleline(&mut menu.filter).hint_text("Filter"));
+        egui::ComboBox::from_id_source("save sort").width(90.).selected_text(menu.sort.name()).show_ui(ui, |ui|{
+            for sort in SaveSort::ALL {
+                ui.selectable_value(&mut menu.sort, sort, sort.name());
+            }
+        });
+        if ui.button(if menu.ascending { "Asc" } else { "Desc" }).clicked(){
+            menu.ascending = !menu.ascending;
+        }
+    });
+
+    let filter = menu.filter.to_lowercase();
+    let mut order: Vec<usize> = (0..save_system.saves.len()).filter(|i| save_system.saves[*i].name().to_lowercase().contains(&filter)).collect();
+    let key = |entry: &SaveEntry| entry.metadata.as_ref().map(|metadata| match menu.sort {
+        SaveSort::Created => metadata.created as i64,
+        SaveSort::Step => metadata.step as i64,
+        SaveSort::Population => metadata.animals as i64,
+        SaveSort::Species => metadata.species as i64,
+        SaveSort::Name => 0,
+    });
+    //saves without metadata sort as if they were the oldest and smallest
+    order.sort_by(|a, b|{
+        let (a, b) = (&save_system.saves[*a], &save_system.saves[*b]);
+        match menu.sort {
+            SaveSort::Name => a.name().to_lowercase().cmp(&b.name().to_lowercase()),
+            _ => key(a).cmp(&key(b)),
+        }
+    });
+    if !menu.ascending {
+        order.reverse();
+    }
+    let order: Vec<String> = order.into_iter().map(|i| save_system.saves[i].file.clone()).collect();
+
+    egui::ScrollArea::vertical().max_height(240.).show(ui, |ui| {
+        for file in order {
+            //a save deleted or renamed this frame is gone from the list
+            let Some(i) = save_system.saves.iter().position(|entry| entry.file == file) else {
+                continue;
+            };
+            if menu.renaming.as_ref().is_some_and(|(renaming, _)| *renaming == file) {
+                ui.horizontal(|ui|{
+                    let (_, name) = menu.renaming.as_mut().unwrap();
+                    ui.add_sized([180., 30.], egui::TextEdit::singleline(name));
+                    if ui.add_sized([50., 30.], egui::Button::new("Rename")).clicked(){
+                        let (file, name) = menu.renaming.take().unwrap();
+                        let renamed = save_system.rename(&file, &name);
+                        save_system.report(renamed);
+                    }
+                    if ui.add_sized([50., 30.], egui::Button::new("Cancel")).clicked(){
+                        menu.renaming = None;
+                    }
+                });
+            }
+            else if ui.add_sized([288., 40.], egui::Button::new(RichText::new(["Load", save_system.saves[i].name()].join(" ")).heading())).clicked(){
+                sim_params.save_file = file.clone();
+                *state = LoadSave;
+            }
+
+            if let Some(metadata) = &save_system.saves[i].metadata {
+                ui.label(format!("{}  step {}  {}x{}", date(metadata.created), metadata.step, metadata.world_size[0], metadata.world_size[1]));
+                ui.label(format!("{} animals  {} species  seed {}", metadata.animals, metadata.species, metadata.seed));
+                if metadata.autosave {
+                    ui.label(RichText::new("Autosave").weak());
+                }
+            }
+            else {
+                ui.label(RichText::new(&save_system.saves[i].file).weak());
+            }
+
+            ui.horizontal(|ui|{
+                if ui.button("Rename").clicked(){
+                    menu.renaming = Some((file.clone(), save_system.saves[i].name().to_string()));
+                }
+                if ui.button("Replay").on_hover_text("Re-run from the start using the event log").clicked(){
+                    sim_params.save_file = file.clone();
+                    *state = ReplaySave;
+                }
+                if ui.button("Duplicate").clicked(){
+                    let duplicated = save_system.duplicate(&file);
+                    save_system.report(duplicated);
+                }
+                if ui.button("JSON").on_hover_text("Export a copy as JSON").clicked(){
+                    let exported = save_system.export_json(&file);
+                    save_system.report(exported);
+                }
+                if ui.button("X").clicked(){
+                    menu.renaming = None;
+                    let deleted = save_system.delete(&file);
+                    save_system.report(deleted);
+                }
+            });
+            ui.separator();
+        }
+        if save_system.saves.is_empty(){
+            ui.label(RichText::new("No Saves").heading());
+        }
+    });
+}
+
+//seconds since the unix epoch as a utc date and time
+fn date(seconds: u64) -> String{
+    let days = (seconds / 86400) as i64;
+    let time = seconds % 86400;
+
+    //days to a civil date, from Howard Hinnant's algorithm
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{}-{:02}-{:02} {:02}:{:02}", year, month, day, time / 3600, time % 3600 / 60)
+}
@@ -1,4 +1,5 @@
 use std::{iter, mem};
+use std::path::PathBuf;
 use std::sync::Arc;
 use egui::Context;
 use egui_wgpu::{ScreenDescriptor};
@@ -10,12 +11,13 @@ use winit::window::Window;
 use crate::environment::animal::Animal;
 use crate::rendering::gui::{EguiRenderer, gui, main_menu_gui};
 use crate::utilities::simulation_parameters::SimParams;
-use crate::utilities::statistics::Stats;
 use crate::rendering::camera::Camera;
 use crate::rendering::instance::Instance;
 use crate::utilities::highlighter::Highlighter;
+use crate::utilities::timeline::Timeline;
 use crate::utilities::save_system::SaveSystem;
 use crate::utilities::state::State;
+use crate::world::World;
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -79,7 +81,7 @@ struct Buffers{
 }
 
 impl Renderer {
-    pub async fn new(window: Arc<Window>)->Self{
+    pub async fn new(window: Arc<Window>, stats_dir: PathBuf)->Self{
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: wgpu::Backends::all(),
             ..Default::default()
@@ -347,6 +349,7 @@ impl Renderer {
             None,
             1,
             &window,
+            stats_dir,
         );
 
         let circles = device.create_buffer(&wgpu::BufferDescriptor{
@@ -396,7 +399,7 @@ impl Renderer {
         }
     }
 
-    pub fn render(&mut self, stats: &mut Stats,sim_params: &mut SimParams,animal: &Option<Animal>,state: &mut State,highlighter: &mut Highlighter) -> Result<(), wgpu::SurfaceError> {
+    pub fn render(&mut self, world: &mut World,timeline: &mut Timeline,animal: &Option<Animal>,state: &mut State,highlighter: &mut Highlighter) -> Result<(), wgpu::SurfaceError> {
         let output = self.surface.get_current_texture()?;
         let view = output.texture.create_view(&TextureViewDescriptor {
             label: None,
@@ -471,8 +474,8 @@ impl Renderer {
             &view,
             screen_descriptor,
             gui,
-            stats,
-            sim_params,
+            world,
+            timeline,
             animal,
             state,
             highlighter
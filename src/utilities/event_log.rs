@@ -0,0 +1,245 @@
+use std::fmt;
+use std::sync::Arc;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+use crate::environment::animal::DeathCause;
+use crate::utilities::simulation_parameters::{BuildSettings, SimParams};
+
+#[derive(Clone,PartialEq,Serialize,Deserialize)]
+pub enum Event{
+    //the parameters a new world was created with, always the first event
+    Start(SimParams),
+    //inputs, which a replay feeds back in at the same step
+    Params(SimParams),
+    //the pen painted with, which logs from before it was kept here leave to the parameters
+    Paint{ pos: [f32;2], place: bool, #[serde(default)] build: Option<BuildSettings> },
+    //outcomes, which a replay checks it reproduces
+    Birth{ id: usize, parent: Option<usize>, #[serde(default)] mate: Option<usize> },
+    Death{ id: usize, cause: DeathCause },
+    Speciation{ species: usize, parent: usize },
+}
+impl Event{
+    pub fn is_input(&self) -> bool{
+        matches!(self, Event::Start(_) | Event::Params(_) | Event::Paint{..})
+    }
+}
+impl fmt::Display for Event{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        match self {
+            Event::Start(sim_params) => write!(f, "World created with seed {}", sim_params.world.seed),
+            Event::Params(_) => write!(f, "Parameters changed"),
+            Event::Paint{ pos, place: true, .. } => write!(f, "Painted at {:.1}, {:.1}", pos[0], pos[1]),
+            Event::Paint{ pos, place: false, .. } => write!(f, "Erased at {:.1}, {:.1}", pos[0], pos[1]),
+            Event::Birth{ id, parent: Some(parent), mate: Some(mate) } => write!(f, "Animal {} born to {} and {}", id, parent, mate),
+            Event::Birth{ id, parent: Some(parent), mate: None } => write!(f, "Animal {} born to {}", id, parent),
+            Event::Birth{ id, parent: None, .. } => write!(f, "Animal {} spawned", id),
+            Event::Death{ id, cause } => write!(f, "Animal {} died of {}", id, cause.name().to_lowercase()),
+            Event::Speciation{ species, parent } => write!(f, "Species {} split from species {}", species, parent),
+        }
+    }
+}
+
+//inputs are logged with the step they were made before, outcomes with the step they happened in
+#[derive(Clone,PartialEq,Serialize,Deserialize)]
+pub struct LoggedEvent{
+    pub step: i32,
+    pub event: Event,
+}
+
+//events are kept in chunks of this many, which are never changed once full
+const CHUNK: usize = 4096;
+
+//everything that has happened to a world since it was created, only ever appended to
+//full chunks are shared rather than copied, so cloning the log for a save only copies the events since the last full chunk
+#[derive(Default,Clone,Deserialize)]
+#[serde(from = "StoredLog")]
+pub struct EventLog{
+    chunks: Vec<Arc<Vec<LoggedEvent>>>,
+    recent: Vec<LoggedEvent>,
+}
+impl EventLog{
+    pub fn push(&mut self, step: i32, event: Event){
+        self.recent.push(LoggedEvent{ step, event });
+        if self.recent.len() == CHUNK {
+            self.chunks.push(Arc::new(std::mem::take(&mut self.recent)));
+        }
+    }
+    pub fn events(&self) -> impl DoubleEndedIterator<Item = &LoggedEvent> + '_{
+        self.chunks.iter().flat_map(|chunk| chunk.iter()).chain(self.recent.iter())
+    }
+    pub fn len(&self) -> usize{
+        self.chunks.len() * CHUNK + self.recent.len()
+    }
+    pub fn is_empty(&self) -> bool{
+        self.len() == 0
+    }
+    //forgets everything after the first len events, for when a world is rewound
+    pub fn truncate(&mut self, len: usize){
+        let (full, rest) = (len / CHUNK, len % CHUNK);
+        if full < self.chunks.len() {
+            self.recent = self.chunks[full][..rest].to_vec();
+            self.chunks.truncate(full);
+        }
+        else if full == self.chunks.len() {
+            self.recent.truncate(rest);
+        }
+    }
+    //the parameters the world was created with, None if the log doesn't go back to the start
+    pub fn start(&self) -> Option<&SimParams>{
+        match self.events().next().map(|logged| &logged.event) {
+            Some(Event::Start(sim_params)) => Some(sim_params),
+            _ => None,
+        }
+    }
+    //the parameters as they were last logged
+    pub fn params(&self) -> Option<&SimParams>{
+        self.events().rev().find_map(|logged| match &logged.event {
+            Event::Start(sim_params) | Event::Params(sim_params) => Some(sim_params),
+            _ => None,
+        })
+    }
+    //one event per line as json
+    pub fn to_json_lines(&self) -> String{
+        self.events().filter_map(|logged| serde_json::to_string(logged).ok()).map(|line| line + "\n").collect()
+    }
+}
+
+//the log is written as one list of events however it is chunked
+impl Serialize for EventLog{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>{
+        struct Events<'a>(&'a EventLog);
+        impl Serialize for Events<'_>{
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>{
+                serializer.collect_seq(self.0.events())
+            }
+        }
+
+        let mut log = serializer.serialize_struct("EventLog", 1)?;
+        log.serialize_field("events", &Events(self))?;
+        log.end()
+    }
+}
+#[derive(Deserialize)]
+struct StoredLog{
+    events: Vec<LoggedEvent>,
+}
+impl From<StoredLog> for EventLog{
+    fn from(stored: StoredLog) -> Self{
+        let mut log = EventLog::default();
+        stored.events.into_iter().for_each(|logged| log.push(logged.step, logged.event));
+        log
+    }
+}
+
+//where a replay first stopped matching the log it was replaying
+#[derive(Clone)]
+pub struct Divergence{
+    pub step: i32,
+    pub expected: Option<LoggedEvent>,
+    pub found: Option<LoggedEvent>,
+}
+impl fmt::Display for Divergence{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        let describe = |logged: &Option<LoggedEvent>| logged.as_ref().map(|logged| format!("{} at step {}", logged.event, logged.step)).unwrap_or("nothing".to_string());
+        write!(f, "diverged at step {}, expected {} but found {}", self.step, describe(&self.expected), describe(&self.found))
+    }
+}
+
+//a log being fed back into a world re-run from its start
+pub struct Replay{
+    expected: Vec<LoggedEvent>,
+    //the next event whose step hasn't been reached yet
+    next: usize,
+    pub end_step: i32,
+    pub divergence: Option<Divergence>,
+}
+impl Replay{
+    pub fn new(log: EventLog, end_step: i32) -> Self{
+        Self{
+            expected: log.events().cloned().collect(),
+            next: 0,
+            end_step,
+            divergence: None,
+        }
+    }
+
+    //the inputs logged before the given step that haven't been applied yet
+    pub fn inputs(&mut self, step: i32) -> Vec<Event>{
+        let mut inputs = vec![];
+        while let Some(logged) = self.expected.get(self.next).filter(|logged| logged.step <= step) {
+            //the start is applied by creating the world
+            if logged.event.is_input() && self.next > 0 {
+                inputs.push(logged.event.clone());
+            }
+            self.next += 1;
+        }
+        inputs
+    }
+
+    //compares the event logged at index against the one expected there
+    //only up to the end step, after which the replay carries on as an ordinary run
+    pub fn check(&mut self, index: usize, found: &LoggedEvent){
+        let expected = self.expected.get(index);
+        if self.divergence.is_none() && found.step < self.end_step && expected != Some(found) {
+            self.divergence = Some(Divergence{ step: found.step, expected: expected.cloned(), found: Some(found.clone()) });
+        }
+    }
+
+    //at the end an event the replay never produced is also a divergence
+    pub fn finish(&mut self, produced: usize){
+        if self.divergence.is_none() {
+            if let Some(expected) = self.expected.get(produced).filter(|expected| expected.step < self.end_step) {
+                self.divergence = Some(Divergence{ step: expected.step, expected: Some(expected.clone()), found: None });
+            }
+        }
+    }
+
+    pub fn finished(&self, step: i32) -> bool{
+        step >= self.end_step
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn log(len: usize) -> EventLog{
+        let mut log = EventLog::default();
+        (0..len).for_each(|id| log.push(id as i32, Event::Birth{ id, parent: None, mate: None }));
+        log
+    }
+
+    #[test]
+    fn clones_share_full_chunks(){
+        let log = log(CHUNK * 2 + 10);
+        let clone = log.clone();
+        assert!(log.chunks.iter().zip(&clone.chunks).all(|(a, b)| Arc::ptr_eq(a, b)));
+        assert!(clone.events().eq(log.events()));
+        assert_eq!(clone.len(), CHUNK * 2 + 10);
+    }
+
+    #[test]
+    fn truncate_across_chunks(){
+        for len in [0, 5, CHUNK, CHUNK + 5, CHUNK * 2 + 10, CHUNK * 3] {
+            let mut truncated = log(CHUNK * 2 + 10);
+            truncated.truncate(len);
+            assert!(truncated.events().eq(log(len.min(CHUNK * 2 + 10)).events()));
+
+            //it carries on filling chunks from where it was cut back to
+            truncated.push(0, Event::Birth{ id: 0, parent: None, mate: None });
+            assert_eq!(truncated.len(), len.min(CHUNK * 2 + 10) + 1);
+        }
+    }
+
+    #[test]
+    fn written_as_one_list(){
+        let log = log(CHUNK + 5);
+        let json = serde_json::to_value(&log).unwrap();
+        assert_eq!(json["events"].as_array().unwrap().len(), CHUNK + 5);
+
+        let read: EventLog = serde_json::from_value(json).unwrap();
+        assert!(read.events().eq(log.events()));
+        let read: EventLog = rmp_serde::from_slice(&rmp_serde::to_vec_named(&log).unwrap()).unwrap();
+        assert!(read.events().eq(log.events()));
+    }
+}
@@ -0,0 +1,155 @@
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::environment::animal::DeathCause;
+use crate::environment::energy::{Food, FlowKind};
+use crate::environment::genome::GENES;
+use crate::utilities::statistics::{AGE_BIN_MINUTES, Stats};
+
+//writes everything in the stats to csv files for analysis, plus the whole struct as json, each file suffixed with the step
+pub fn export_stats(stats: &Stats, dir: &Path, step: i32) -> io::Result<()>{
+    fs::create_dir_all(dir)?;
+    let file = |name: &str, extension: &str| dir.join([name, "_", &step.to_string(), ".", extension].join(""));
+
+    fs::write(file("stats", "json"), serde_json::to_string(stats)?)?;
+    fs::write(file("populations", "csv"), populations_csv(stats))?;
+    fs::write(file("species", "csv"), species_csv(stats))?;
+    fs::write(file("deaths", "csv"), deaths_csv(stats))?;
+    fs::write(file("energy", "csv"), energy_csv(stats))?;
+    fs::write(file("food_web", "csv"), food_web_csv(stats))?;
+    fs::write(file("distributions", "csv"), distributions_csv(stats))?;
+    fs::write(file("diagnostics", "csv"), diagnostics_csv(stats))?;
+
+    Ok(())
+}
+
+fn column_name(name: &str) -> String{
+    name.to_lowercase().replace(' ', "_")
+}
+
+//one row per graph sample, every population series is sampled together so rows line up by index
+//samples are counted the same way as the graphs' x axis, once per recorded frame rather than per step
+fn populations_csv(stats: &Stats) -> String{
+    let populations = &stats.populations;
+
+    let mut csv = String::from("sample,animals,plants,fruit,herbivores,omnivores,carnivores,average_speed,average_size,richness,shannon,simpson,disparity");
+    GENES.iter().for_each(|gene| csv.push_str(&[",mean_", &column_name(gene.name)].join("")));
+    csv.push('\n');
+
+    let value = |series: &Vec<[f64;2]>, i: usize| series.get(i).map(|sample| sample[1].to_string()).unwrap_or_default();
+    populations.animals.iter().enumerate().for_each(|(i, sample)|{
+        let mut row = vec![
+            sample[0].to_string(),
+            sample[1].to_string(),
+            value(&populations.plants, i),
+            value(&populations.fruit, i),
+            value(&populations.herbivores, i),
+            value(&populations.omnivores, i),
+            value(&populations.carnivores, i),
+            value(&populations.average_speed, i),
+            value(&populations.average_size, i),
+            value(&populations.diversity.richness, i),
+            value(&populations.diversity.shannon, i),
+            value(&populations.diversity.simpson, i),
+            value(&populations.diversity.disparity, i),
+        ];
+        row.extend((0..GENES.len()).map(|gene| populations.average_genes.get(gene).map(|series| value(series, i)).unwrap_or_default()));
+        let _ = writeln!(csv, "{}", row.join(","));
+    });
+    csv
+}
+
+//long format, one row per species per sample
+fn species_csv(stats: &Stats) -> String{
+    let mut csv = String::from("sample,species,population");
+    GENES.iter().for_each(|gene| csv.push_str(&[",mean_", &column_name(gene.name)].join("")));
+    csv.push('\n');
+
+    stats.populations.species.iter().for_each(|(id, series)|{
+        series.population.iter().zip(series.mean_genes.iter()).for_each(|(sample, genes)|{
+            let genes: Vec<String> = genes.iter().map(|gene| gene.to_string()).collect();
+            let _ = writeln!(csv, "{},{},{},{}", sample[0], id, sample[1], genes.join(","));
+        });
+    });
+    csv
+}
+
+fn deaths_csv(stats: &Stats) -> String{
+    let deaths = &stats.life_history.deaths;
+
+    let mut csv = String::from("sample");
+    DeathCause::ALL.iter().for_each(|cause| csv.push_str(&[",", &column_name(cause.name())].join("")));
+    csv.push('\n');
+
+    if let Some(first) = deaths.first() {
+        first.iter().enumerate().for_each(|(i, sample)|{
+            let counts: Vec<String> = deaths.iter().map(|series| series.get(i).map(|sample| sample[1].to_string()).unwrap_or_default()).collect();
+            let _ = writeln!(csv, "{},{}", sample[0], counts.join(","));
+        });
+    }
+    csv
+}
+
+//mean energy and protein per step of every flow, then the stock and the energy the flows don't account for
+fn energy_csv(stats: &Stats) -> String{
+    let energy = &stats.energy;
+
+    let mut csv = String::from("sample");
+    FlowKind::ALL.iter().for_each(|kind| csv.push_str(&[",", &column_name(kind.name()), "_energy,", &column_name(kind.name()), "_protein"].join("")));
+    csv.push_str(",stock,imbalance\n");
+
+    let value = |series: &Vec<[f64;2]>, i: usize| series.get(i).map(|sample| sample[1].to_string()).unwrap_or_default();
+    energy.stock.iter().enumerate().for_each(|(i, sample)|{
+        let mut row = vec![sample[0].to_string()];
+        energy.energy.iter().zip(energy.protein.iter()).for_each(|(energy, protein)|{
+            row.push(value(energy, i));
+            row.push(value(protein, i));
+        });
+        row.push(sample[1].to_string());
+        //the first sample has nothing to compare against so the imbalance series starts one sample later
+        row.push(i.checked_sub(1).map(|i| value(&energy.imbalance, i)).unwrap_or_default());
+        let _ = writeln!(csv, "{}", row.join(","));
+    });
+    csv
+}
+
+fn food_web_csv(stats: &Stats) -> String{
+    let mut csv = String::from("eater,food,energy\n");
+    stats.energy.food_web.iter().for_each(|link|{
+        let food = match link.food {
+            Food::Plants => "plants".to_string(),
+            Food::Fruit => "fruit".to_string(),
+            Food::Species(id) => id.to_string(),
+        };
+        let _ = writeln!(csv, "{},{},{}", link.eater, food, link.energy);
+    });
+    csv
+}
+
+//long format, one row per histogram bin, where bin is the centre of the bin in the histogram's own units
+fn distributions_csv(stats: &Stats) -> String{
+    let life_history = &stats.life_history;
+
+    let mut csv = String::from("distribution,bin,count\n");
+    let mut histogram = |name: &str, counts: &[f64], start: f64, width: f64|{
+        counts.iter().enumerate().for_each(|(i, count)|{
+            let _ = writeln!(csv, "{},{},{}", name, start + i as f64 * width, count);
+        });
+    };
+    stats.distributions.histograms().iter().for_each(|distribution| histogram(&column_name(distribution.name), distribution.counts, distribution.start, distribution.width));
+    histogram("age_at_death_minutes", &life_history.age_at_death, AGE_BIN_MINUTES as f64 / 2., AGE_BIN_MINUTES as f64);
+    histogram("offspring", &life_history.offspring, 0., 1.);
+    csv
+}
+
+fn diagnostics_csv(stats: &Stats) -> String{
+    let mut csv = String::from("fps,used_memory,total_memory,total_cpu_usage");
+    (0..stats.cpu_usages.len()).for_each(|i| { let _ = write!(csv, ",cpu_{}", i + 1); });
+    csv.push('\n');
+
+    let _ = write!(csv, "{},{},{},{}", stats.fps, stats.used_mem, stats.tot_mem, stats.tot_cpu_usage);
+    stats.cpu_usages.iter().for_each(|usage| { let _ = write!(csv, ",{}", usage); });
+    csv.push('\n');
+    csv
+}
@@ -0,0 +1,144 @@
+use serde_json::{Map, Value};
+use crate::environment::animal::MEMORY_CELLS;
+use crate::environment::genome::{Gene, GENES};
+use crate::environment::neural_network::{Innovations, Network};
+use crate::utilities::random::{stream, Stream};
+use crate::utilities::simulation_parameters::{SimParams, DEFAULT_SEED};
+
+//brings a json save written before the save format was versioned up to date as raw json, before it is deserialized
+//each change is only made where the old layout is found, so json exported by this version passes through untouched
+pub fn migrate_json(save: &mut Value) -> Result<(), serde_json::Error>{
+    if !save.is_object() {
+        return Ok(());
+    }
+    //settings added since then take their defaults, including the seed
+    fill_missing(&mut save["sim_params"], serde_json::to_value(SimParams::default())?);
+    let seed = save["sim_params"]["world"]["seed"].as_u64().unwrap_or(DEFAULT_SEED);
+
+    //every old brain had the same layout, which is the one animals are spawned with now less the memory cells
+    let mut innovations = Innovations::default();
+    let template = serde_json::to_value(Network::zero(&[11 + MEMORY_CELLS, 11, 4 + MEMORY_CELLS], &mut innovations))?;
+
+    save["animals"]["animals"].as_array_mut().into_iter().flatten().for_each(|animal| migrate_animal(animal, &template));
+    save["eggs"]["eggs"].as_array_mut().into_iter().flatten().for_each(|egg| migrate_animal(&mut egg["animal"], &template));
+    save["species_list"]["species"].as_array_mut().into_iter().flatten().for_each(|species| migrate_animal(&mut species["specimen"], &template));
+
+    insert_missing(&mut save["animals"], "innovations", serde_json::to_value(innovations)?);
+    insert_missing(&mut save["animals"], "rng", serde_json::to_value(stream(seed, Stream::Animals))?);
+    insert_missing(&mut save["plants"], "rng", serde_json::to_value(stream(seed, Stream::Plants))?);
+    insert_missing(&mut save["fruits"], "rng", serde_json::to_value(stream(seed, Stream::Fruit))?);
+    Ok(())
+}
+
+fn migrate_animal(animal: &mut Value, template: &Value){
+    if !animal.is_object() {
+        return;
+    }
+    let brain = &mut animal["brain"];
+    if brain["network"]["layers"][1]["neurons"][0].get("weights").is_some() {
+        brain["network"] = rebuild_network(&brain["network"], template);
+    }
+    insert_missing(brain, "memory", vec![0.0; MEMORY_CELLS].into());
+
+    //the traits an animal was born with were spread across its stats before they were gathered into a genome
+    if animal.get("genome").is_none() {
+        let mut genes: Vec<f32> = GENES.iter().map(|gene| gene.min).collect();
+        let mut set = |gene: Gene, value: &Value| {
+            if let Some(value) = value.as_f64() {
+                genes[gene as usize] = value as f32;
+            }
+        };
+        set(Gene::Speed, &animal["max_stats"]["speed"]);
+        set(Gene::Size, &animal["max_stats"]["size"]);
+        set(Gene::Attack, &animal["max_stats"]["attack"]);
+        set(Gene::OffspringInvestment, &animal["reproduction_stats"]["offspring_investment"]);
+        set(Gene::CarnivoreFactor, &animal["combat_stats"]["carnivore_factor"]);
+        set(Gene::AnimalVision, &animal["senses"]["animal_vision"]);
+        set(Gene::PlantVision, &animal["senses"]["plant_vision"]);
+        set(Gene::FruitVision, &animal["senses"]["fruit_vision"]);
+        set(Gene::RockVision, &animal["senses"]["rock_vision"]);
+        set(Gene::TempTolerance, &animal["temp_tolerance"]);
+        set(Gene::IdealTemp, &animal["ideal_temp"]);
+        set(Gene::Hue, &animal["hue"]);
+
+        animal["genome"] = Map::from_iter([("genes".to_string(), genes.into())]).into();
+        if let Some(animal) = animal.as_object_mut() {
+            animal.remove("max_stats");
+        }
+    }
+}
+
+//old neurons held a weight for every neuron in the layer before, which become the template's connections in the same order
+//the memory inputs and outputs the template adds are left unconnected
+fn rebuild_network(network: &Value, template: &Value) -> Value{
+    let mut rebuilt = template.clone();
+    let layers = network["layers"].as_array().into_iter().flatten();
+    for (old_layer, layer) in layers.zip(rebuilt["layers"].as_array_mut().into_iter().flatten()) {
+        let old_neurons = old_layer["neurons"].as_array().into_iter().flatten();
+        for (old_neuron, neuron) in old_neurons.zip(layer["neurons"].as_array_mut().into_iter().flatten()) {
+            neuron["activation"] = old_neuron["activation"].clone();
+            neuron["bias"] = old_neuron["bias"].clone();
+
+            let weights = old_neuron["weights"].as_array().into_iter().flatten();
+            for (weight, connection) in weights.zip(neuron["connections"].as_array_mut().into_iter().flatten()) {
+                connection["weight"] = weight.clone();
+            }
+        }
+    }
+    rebuilt
+}
+
+fn insert_missing(object: &mut Value, key: &str, value: Value){
+    if let Some(object) = object.as_object_mut() {
+        object.entry(key).or_insert(value);
+    }
+}
+
+//fills in every field missing from value, at any depth, from defaults
+fn fill_missing(value: &mut Value, defaults: Value){
+    let (Some(object), Value::Object(defaults)) = (value.as_object_mut(), defaults) else { return; };
+    for (key, default) in defaults {
+        match object.get_mut(&key) {
+            Some(value) => fill_missing(value, default),
+            None => { object.insert(key, default); }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn fills_missing_settings_without_overwriting(){
+        let mut sim_params = json!({ "world": { "width": 40.0 }, "autosave": 10 });
+        fill_missing(&mut sim_params, serde_json::to_value(SimParams::default()).unwrap());
+
+        let sim_params: SimParams = serde_json::from_value(sim_params).unwrap();
+        assert_eq!(sim_params.world.width, 40.0);
+        assert_eq!(sim_params.autosave, 10);
+        assert_eq!(sim_params.world.seed, DEFAULT_SEED);
+        assert_eq!(sim_params.world.height, SimParams::default().world.height);
+    }
+
+    #[test]
+    fn weights_become_connections(){
+        let mut innovations = Innovations::default();
+        let template = serde_json::to_value(Network::zero(&[3, 2], &mut innovations)).unwrap();
+        let old = json!({ "layers": [
+            { "neurons": [{ "activation": 1.0, "bias": 0.0, "weights": [] }, { "activation": 0.5, "bias": 0.0, "weights": [] }] },
+            { "neurons": [{ "activation": 0.0, "bias": 0.25, "weights": [0.5, -0.5] }] },
+        ]});
+
+        let network = rebuild_network(&old, &template);
+        let neuron = &network["layers"][1]["neurons"][0];
+        assert_eq!(neuron["bias"], 0.25);
+        assert_eq!(neuron["connections"][0]["weight"], 0.5);
+        assert_eq!(neuron["connections"][1]["weight"], -0.5);
+        //the input the old layout didn't have stays unconnected, as does the output
+        assert_eq!(neuron["connections"][2]["weight"], 0.0);
+        assert_eq!(network["layers"][1]["neurons"][1]["connections"][0]["weight"], 0.0);
+        assert_eq!(network["layers"][0]["neurons"][1]["activation"], 0.5);
+    }
+}
@@ -4,3 +4,8 @@ pub mod save_system;
 pub mod statistics;
 pub mod state;
 pub mod highlighter;
+pub mod random;
+pub mod export;
+pub mod event_log;
+pub mod timeline;
+pub mod migration;
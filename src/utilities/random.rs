@@ -0,0 +1,19 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+pub type SimRng = ChaCha8Rng;
+
+//each subsystem draws from its own stream of the world seed so that extra draws in one don't shift the others
+pub enum Stream{
+    Terrain,
+    Spawners,
+    Animals,
+    Plants,
+    Fruit,
+}
+
+pub fn stream(seed: u64, stream: Stream) -> SimRng{
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(stream as u64);
+    rng
+}
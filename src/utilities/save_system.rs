@@ -1,97 +1,583 @@
-use std::fs;
+use std::error::Error;
+use std::{fmt, fs, io};
 use std::fs::File;
-use std::ops::Index;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
 use crate::environment::animal::Animals;
+use crate::environment::collisions::GridCells;
 use crate::environment::eggs::Eggs;
 use crate::environment::fruit::{Fruits, FruitSpawners};
 use crate::environment::plants::{Plants, PlantSpawners};
 use crate::environment::rocks::RockMap;
-use crate::utilities::simulation_parameters::SimParams;
+use crate::utilities::event_log::EventLog;
+use crate::utilities::migration::migrate_json;
+use crate::utilities::simulation_parameters::{SimParams, TemperatureSettings};
 use crate::environment::species::SpeciesList;
 use crate::utilities::statistics::Stats;
 
+//saves start with the magic bytes and a little endian format version, then from version 2 the length of the metadata and the metadata itself as MessagePack
+//the world follows as zstd compressed MessagePack
+//fields are written by name so that, as with json, new fields marked #[serde(default)] don't need a version bump
+//saves without the header are legacy json saves
+const MAGIC: &[u8;4] = b"ESIM";
+pub const SAVE_VERSION: u32 = 2;
+const COMPRESSION_LEVEL: i32 = 3;
+//saves are written to a hidden temporary file first and renamed over the real one, so a crash never leaves half a save
+const TEMP_EXTENSION: &str = ".tmp";
+
+#[derive(Debug)]
+pub enum SaveError{
+    Io(io::Error),
+    //the header or compressed data of a binary save is damaged
+    Corrupted(io::Error),
+    Encode(rmp_serde::encode::Error),
+    Decode(rmp_serde::decode::Error),
+    Json(serde_json::Error),
+    NewerVersion(u32),
+    NoSuchSave(String),
+    NoSnapshot(usize),
+    NoAutosave,
+    NoEventLog,
+}
+impl fmt::Display for SaveError{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        match self {
+            SaveError::Io(err) => write!(f, "Unable to access save: {}", err),
+            SaveError::Corrupted(err) => write!(f, "Save is corrupted: {}", err),
+            SaveError::Encode(err) => write!(f, "Unable to write save: {}", err),
+            SaveError::Decode(err) => write!(f, "Save is corrupted or outdated: {}", err),
+            SaveError::Json(err) => write!(f, "JSON save is corrupted or outdated: {}", err),
+            SaveError::NewerVersion(version) => write!(f, "Save was written by a newer version (format {}, this version reads up to {})", version, SAVE_VERSION),
+            SaveError::NoSuchSave(file) => write!(f, "There is no save {}", file),
+            SaveError::NoSnapshot(i) => write!(f, "There is no snapshot {}", i),
+            SaveError::NoAutosave => write!(f, "There is no autosave that can be resumed"),
+            SaveError::NoEventLog => write!(f, "Save has no event log going back to its start so it can't be replayed"),
+        }
+    }
+}
+impl Error for SaveError{}
+impl From<io::Error> for SaveError{
+    fn from(err: io::Error) -> Self{ SaveError::Io(err) }
+}
+impl From<rmp_serde::encode::Error> for SaveError{
+    fn from(err: rmp_serde::encode::Error) -> Self{ SaveError::Encode(err) }
+}
+impl From<rmp_serde::decode::Error> for SaveError{
+    fn from(err: rmp_serde::decode::Error) -> Self{ SaveError::Decode(err) }
+}
+impl From<serde_json::Error> for SaveError{
+    fn from(err: serde_json::Error) -> Self{ SaveError::Json(err) }
+}
+
+//a summary of a save stored ahead of the world so the load menu can show it without reading everything
+#[derive(Serialize,Deserialize,Clone)]
+pub struct SaveMetadata{
+    pub name: String,
+    //seconds since the unix epoch
+    pub created: u64,
+    pub step: i32,
+    pub world_size: [f32;2],
+    pub animals: usize,
+    pub plants: usize,
+    pub species: usize,
+    pub seed: u64,
+    #[serde(default)]
+    pub autosave: bool,
+}
+impl SaveMetadata{
+    pub fn new(save: &SimulationSave) -> Self{
+        Self{
+            name: save.name.clone(),
+            created: SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0),
+            step: save.step,
+            world_size: [save.sim_params.world.width, save.sim_params.world.height],
+            animals: save.animals.count() + save.eggs.count(),
+            plants: save.plants.count(),
+            species: save.species_list.count(),
+            seed: save.sim_params.world.seed,
+            autosave: false,
+        }
+    }
+}
+
+//a file in the saves directory, json and version 1 saves have no metadata
+//saves are looked up by file rather than by their place in the list, which shifts whenever one is deleted
+pub struct SaveEntry{
+    pub file: String,
+    pub metadata: Option<SaveMetadata>,
+}
+impl SaveEntry{
+    pub fn is_autosave(&self) -> bool{
+        self.metadata.as_ref().is_some_and(|metadata| metadata.autosave)
+    }
+    pub fn name(&self) -> &str{
+        self.metadata.as_ref().map(|metadata| metadata.name.as_str()).unwrap_or(self.file.trim_end_matches(".json"))
+    }
+    //autosaves are rotated per world, and as worlds share the default seed a world is told apart by its name and seed together
+    fn same_world(&self, metadata: &SaveMetadata) -> bool{
+        self.metadata.as_ref().is_some_and(|own| own.name == metadata.name && own.seed == metadata.seed)
+    }
+}
+
+//work for the save thread, which is done in the order it was queued
+enum Task{
+    Write(Job),
+    //a world that is only encoded and sent back, for timeline snapshots
+    Encode(SimulationSave, Sender<Encoded>),
+}
+pub type Encoded = Result<Vec<u8>, SaveError>;
+
+//a world waiting to be encoded and written by the save thread, keep is set for autosaves
+struct Job{
+    file: String,
+    metadata: SaveMetadata,
+    save: SimulationSave,
+    keep: Option<usize>,
+}
+struct Written{
+    file: String,
+    metadata: SaveMetadata,
+    keep: Option<usize>,
+    result: Result<(), SaveError>,
+}
+
 pub struct SaveSystem{
-    pub saves: Vec<String>,
-    save_number: usize,
+    pub saves: Vec<SaveEntry>,
+    dir: PathBuf,
+    //the last thing that went wrong, shown in the main menu until dismissed
+    pub last_error: Option<SaveError>,
+    //saves are encoded and written in order on one thread so large worlds don't hold up the frame
+    tasks: Sender<Task>,
+    written: Receiver<Written>,
+    in_flight: Vec<String>,
 }
 
 impl Default for SaveSystem{
     fn default() -> Self {
-        let saves: Vec<String> = fs::read_dir("saves").unwrap().map(|file|{
-            file.unwrap().file_name().into_string().unwrap()
-        }).collect();
-        Self{
-            save_number: saves.len(),
-            saves,
-        }
+        Self::new(Path::new("saves"))
     }
 }
 
 impl SaveSystem{
-    pub fn load(&self,save_id: usize) -> SimulationSave{
-        let data = fs::read_to_string(["saves/",self.saves.index(save_id)].join("")).expect("Unable to read file");
-
-        serde_json::from_str(&data).unwrap()
-    }
-
-    pub fn save(&mut self,step: i32, animals: Animals,plants: Plants, fruits: Fruits,eggs: Eggs,species_list: SpeciesList,stats: Stats,sim_params: SimParams,rock_map: RockMap, fruit_spawners: FruitSpawners,plant_spawners: PlantSpawners){
-        let save = SimulationSave{
-            step,
-            animals,
-            plants,
-            fruits,
-            eggs,
-            species_list,
-            stats,
-            sim_params,
-            rock_map,
-            fruit_spawners,
-            plant_spawners,
+    //the directory is only created once something is saved, a failure to list it is kept in last_error
+    pub fn new(dir: &Path) -> Self{
+        let (tasks, task_receiver) = mpsc::channel::<Task>();
+        let (written_sender, written) = mpsc::channel();
+
+        let thread_dir = dir.to_path_buf();
+        thread::spawn(move ||{
+            for task in task_receiver {
+                match task {
+                    Task::Write(job) => {
+                        let result = encode(&job.save, &job.metadata).and_then(|data| write(&thread_dir, &job.file, &data));
+                        if written_sender.send(Written{ file: job.file, metadata: job.metadata, keep: job.keep, result }).is_err() {
+                            break;
+                        }
+                    }
+                    //nobody is left to receive a snapshot whose timeline has since been cleared, which is fine
+                    Task::Encode(save, reply) => {
+                        let _ = reply.send(encode(&save, &SaveMetadata::new(&save)));
+                    }
+                }
+            }
+        });
+
+        let mut save_system = Self{
+            saves: vec![],
+            dir: dir.to_path_buf(),
+            last_error: None,
+            tasks,
+            written,
+            in_flight: vec![],
+        };
+        if let Err(err) = save_system.refresh() {
+            save_system.last_error = Some(err);
+        }
+        save_system
+    }
+
+    //relists the saves directory, which is treated as empty if it doesn't exist yet
+    pub fn refresh(&mut self) -> Result<(), SaveError>{
+        let files: Vec<String> = match fs::read_dir(&self.dir) {
+            //temporary files are left over from a write that never finished
+            Ok(entries) => entries.filter_map(|entry| entry.ok()?.file_name().into_string().ok()).filter(|file| !file.ends_with(TEMP_EXTENSION)).collect(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => vec![],
+            Err(err) => return Err(err.into()),
+        };
+        self.saves = files.into_iter().map(|file|{
+            //a save that can't be read is still listed so it can be deleted
+            let metadata = read_metadata(&self.dir.join(&file)).ok().flatten();
+            SaveEntry{ file, metadata }
+        }).collect();
+        Ok(())
+    }
+
+    fn index(&self, file: &str) -> Result<usize, SaveError>{
+        self.saves.iter().position(|entry| entry.file == file).ok_or_else(|| SaveError::NoSuchSave(file.to_string()))
+    }
+
+    //a file name made from the save name that no other save is using
+    fn free_file(&self, name: &str, extension: &str) -> String{
+        let mut stem: String = name.trim().chars().map(|c| if c.is_alphanumeric() || c == ' ' || c == '-' || c == '_' { c } else { '_' }).collect();
+        if stem.is_empty() {
+            stem = "save".to_string();
+        }
+
+        let mut file = [stem.as_str(), extension].join("");
+        let mut copy = 1;
+        while self.saves.iter().any(|entry| entry.file == file) || self.in_flight.contains(&file) || self.dir.join(&file).exists() {
+            copy += 1;
+            file = format!("{} ({}){}", stem, copy, extension);
+        }
+        file
+    }
+
+    pub fn load(&self, file: &str) -> Result<SimulationSave, SaveError>{
+        let entry = &self.saves[self.index(file)?];
+        let data = fs::read(self.dir.join(&entry.file))?;
+
+        //the name is only rewritten in the metadata when a save is renamed
+        let mut save = decode(&data)?;
+        if let Some(metadata) = &entry.metadata {
+            save.name = metadata.name.clone();
+        }
+        Ok(save)
+    }
+
+    fn write(&self, file: &str, data: &[u8]) -> Result<(), SaveError>{
+        write(&self.dir, file, data)
+    }
+
+    //hands the save to the save thread and returns the file it will be written to, it is listed once poll or flush sees that it has been written
+    pub fn save(&mut self,save: SimulationSave) -> Result<String, SaveError>{
+        let metadata = SaveMetadata::new(&save);
+        let file = self.free_file(&save.name, "");
+        self.queue(Job{ file, metadata, save, keep: None })
+    }
+
+    //saves like any other save, then once written deletes the world's oldest autosaves until only keep are left
+    pub fn autosave(&mut self, save: SimulationSave, keep: usize) -> Result<String, SaveError>{
+        let metadata = SaveMetadata{ autosave: true, ..SaveMetadata::new(&save) };
+        let file = self.free_file(&["Autosave ", &save.name].join(""), "");
+        self.queue(Job{ file, metadata, save, keep: Some(keep) })
+    }
+
+    fn queue(&mut self, job: Job) -> Result<String, SaveError>{
+        let file = job.file.clone();
+        self.send(Task::Write(job))?;
+        self.in_flight.push(file.clone());
+        Ok(file)
+    }
+
+    //encodes a world on the save thread without writing it, sending the result back on reply once done
+    pub fn queue_encode(&self, save: SimulationSave, reply: Sender<Encoded>) -> Result<(), SaveError>{
+        self.send(Task::Encode(save, reply))
+    }
+
+    fn send(&self, task: Task) -> Result<(), SaveError>{
+        self.tasks.send(task).map_err(|_| io::Error::other("the save thread has stopped").into())
+    }
+
+    pub fn saving(&self) -> bool{
+        !self.in_flight.is_empty()
+    }
+
+    //lists the saves the save thread has finished with, errors are kept in last_error
+    pub fn poll(&mut self){
+        while let Ok(written) = self.written.try_recv() {
+            self.finish(written);
+        }
+    }
+
+    //waits for every queued save to be written
+    pub fn flush(&mut self){
+        while self.saving() {
+            match self.written.recv() {
+                Ok(written) => self.finish(written),
+                Err(_) => break,
+            }
+        }
+    }
+
+    fn finish(&mut self, written: Written){
+        self.in_flight.retain(|file| *file != written.file);
+        if self.report(written.result).is_none() {
+            return;
+        }
+        self.saves.push(SaveEntry{ file: written.file, metadata: Some(written.metadata.clone()) });
+
+        if let Some(keep) = written.keep {
+            let old: Vec<String> = self.autosaves().into_iter()
+                .filter(|entry| entry.same_world(&written.metadata))
+                .skip(keep.max(1))
+                .map(|entry| entry.file.clone())
+                .collect();
+            for file in old {
+                let deleted = self.delete(&file);
+                self.report(deleted);
+            }
+        }
+    }
+
+    //every world's autosaves, newest first
+    pub fn autosaves(&self) -> Vec<&SaveEntry>{
+        let mut autosaves: Vec<&SaveEntry> = self.saves.iter().filter(|entry| entry.is_autosave()).collect();
+        autosaves.sort_by_key(|entry| entry.metadata.as_ref().map(|metadata| (metadata.created, metadata.step)));
+        autosaves.reverse();
+        autosaves
+    }
+
+    //loads the newest autosave that can still be read, skipping any that were damaged
+    pub fn load_latest_autosave(&self) -> Result<SimulationSave, SaveError>{
+        let mut last_err = SaveError::NoAutosave;
+        for entry in self.autosaves() {
+            match self.load(&entry.file) {
+                Ok(save) => return Ok(save),
+                Err(err) => {
+                    eprintln!("Skipping autosave {}: {}", entry.file, err);
+                    last_err = err;
+                }
+            }
+        }
+        Err(last_err)
+    }
+
+    //writes a save out again as json next to it, which can still be loaded like any other save
+    pub fn export_json(&mut self, file: &str) -> Result<(), SaveError>{
+        let save = self.load(file)?;
+        let file = self.free_file(self.saves[self.index(file)?].name(), ".json");
+
+        self.write(&file, serde_json::to_string(&save)?.as_bytes())?;
+
+        self.saves.push(SaveEntry{ file, metadata: None });
+        Ok(())
+    }
+
+    //saves with metadata are given the new name inside as well, the world itself is copied across without being decoded
+    //a renamed or duplicated autosave becomes an ordinary save so it is never rotated away
+    pub fn rename(&mut self, file: &str, name: &str) -> Result<(), SaveError>{
+        let save_id = self.index(file)?;
+        let entry = &self.saves[save_id];
+        let path = self.dir.join(&entry.file);
+
+        let (file, metadata) = match &entry.metadata {
+            Some(metadata) => {
+                let metadata = SaveMetadata{ name: name.to_string(), autosave: false, ..metadata.clone() };
+                let data = with_metadata(&fs::read(&path)?, &metadata)?;
+
+                let file = self.free_file(name, "");
+                self.write(&file, &data)?;
+                fs::remove_file(&path)?;
+                (file, Some(metadata))
+            }
+            None => {
+                let extension = if entry.file.ends_with(".json") { ".json" } else { "" };
+                let file = self.free_file(name, extension);
+                fs::rename(&path, self.dir.join(&file))?;
+                (file, None)
+            }
         };
 
-        let serialized = serde_json::to_string(&save).unwrap();
+        self.saves[save_id] = SaveEntry{ file, metadata };
+        Ok(())
+    }
+
+    pub fn duplicate(&mut self, file: &str) -> Result<(), SaveError>{
+        let entry = &self.saves[self.index(file)?];
+        let path = self.dir.join(&entry.file);
+        let name = [entry.name(), " copy"].join("");
+
+        let (file, metadata) = match &entry.metadata {
+            Some(metadata) => {
+                let metadata = SaveMetadata{ name: name.clone(), autosave: false, ..metadata.clone() };
+                let data = with_metadata(&fs::read(&path)?, &metadata)?;
 
-        let mut path = ["save_",&self.save_number.to_string()].join("");
-        loop {
-            if self.saves.contains(&path) {
-                self.save_number += 1;
-                path = ["save_",&self.save_number.to_string()].join("")
+                let file = self.free_file(&name, "");
+                self.write(&file, &data)?;
+                (file, Some(metadata))
             }
-            else {
-                break
+            None => {
+                let extension = if entry.file.ends_with(".json") { ".json" } else { "" };
+                let file = self.free_file(&name, extension);
+                self.write(&file, &fs::read(&path)?)?;
+                (file, None)
+            }
+        };
+
+        self.saves.push(SaveEntry{ file, metadata });
+        Ok(())
+    }
+
+    pub fn delete(&mut self, file: &str) -> Result<(), SaveError>{
+        let save_id = self.index(file)?;
+        fs::remove_file(self.dir.join(file))?;
+        self.saves.remove(save_id);
+        Ok(())
+    }
+
+    //keeps an error to show in the main menu, for callers that can't stop to handle it
+    pub fn report<T>(&mut self, result: Result<T, SaveError>) -> Option<T>{
+        match result {
+            Ok(value) => Some(value),
+            Err(err) => {
+                eprintln!("{}", err);
+                self.last_error = Some(err);
+                None
             }
         }
+    }
+}
+
+//writes the file under a temporary name first so that an existing file is only ever replaced by a complete one
+fn write(dir: &Path, file: &str, data: &[u8]) -> Result<(), SaveError>{
+    fs::create_dir_all(dir)?;
+    let temp = dir.join([".", file, TEMP_EXTENSION].join(""));
+
+    let mut temp_file = File::create(&temp)?;
+    temp_file.write_all(data)?;
+    temp_file.sync_all()?;
+    fs::rename(&temp, dir.join(file))?;
+    Ok(())
+}
+
+fn header(version: u32, metadata: &SaveMetadata) -> Result<Vec<u8>, SaveError>{
+    let metadata = rmp_serde::to_vec_named(metadata)?;
+
+    let mut data = MAGIC.to_vec();
+    data.extend_from_slice(&version.to_le_bytes());
+    data.extend_from_slice(&(metadata.len() as u32).to_le_bytes());
+    data.extend(metadata);
+    Ok(data)
+}
+
+//reads a save straight from a file that may be outside the saves directory
+pub fn load_file(path: &Path) -> Result<SimulationSave, SaveError>{
+    decode(&fs::read(path)?)
+}
+
+pub fn encode(save: &SimulationSave, metadata: &SaveMetadata) -> Result<Vec<u8>, SaveError>{
+    let payload = rmp_serde::to_vec_named(save)?;
+
+    let mut data = header(SAVE_VERSION, metadata)?;
+    data.extend(zstd::encode_all(payload.as_slice(), COMPRESSION_LEVEL)?);
+    Ok(data)
+}
+
+//the parts of a binary save
+struct Parts<'a>{
+    version: u32,
+    payload: &'a [u8],
+}
+
+//the version and compressed world of a binary save, None for a json save
+fn split(data: &[u8]) -> Result<Option<Parts<'_>>, SaveError>{
+    let Some(body) = data.strip_prefix(MAGIC) else {
+        return Ok(None);
+    };
+    let corrupted = || SaveError::Corrupted(io::Error::from(io::ErrorKind::UnexpectedEof));
+
+    let (version, body) = body.split_first_chunk::<4>().ok_or_else(corrupted)?;
+    let version = u32::from_le_bytes(*version);
+    if version > SAVE_VERSION {
+        return Err(SaveError::NewerVersion(version));
+    }
+    if version < 2 {
+        return Ok(Some(Parts{ version, payload: body }));
+    }
+
+    let (length, body) = body.split_first_chunk::<4>().ok_or_else(corrupted)?;
+    let length = u32::from_le_bytes(*length) as usize;
+    if body.len() < length {
+        return Err(corrupted());
+    }
+    Ok(Some(Parts{ version, payload: &body[length..] }))
+}
+
+pub fn decode(data: &[u8]) -> Result<SimulationSave, SaveError>{
+    let Some(Parts{ version, payload }) = split(data)? else {
+        let mut save = serde_json::from_slice(data)?;
+        migrate_json(&mut save)?;
+        let mut save = serde_json::from_value(save)?;
+        migrate(&mut save, 0);
+        return Ok(save);
+    };
+
+    let payload = zstd::decode_all(payload).map_err(SaveError::Corrupted)?;
+    let mut save: SimulationSave = rmp_serde::from_slice(&payload)?;
+    migrate(&mut save, version);
+    Ok(save)
+}
+
+//the same save with its metadata replaced, written at the current version
+fn with_metadata(data: &[u8], metadata: &SaveMetadata) -> Result<Vec<u8>, SaveError>{
+    let Some(Parts{ payload, .. }) = split(data)? else {
+        return Err(SaveError::Corrupted(io::Error::new(io::ErrorKind::InvalidData, "json saves have no metadata")));
+    };
+    let mut data = header(SAVE_VERSION, metadata)?;
+    data.extend_from_slice(payload);
+    Ok(data)
+}
 
-        File::create(["saves/",&path].join("")).unwrap();
-        fs::write(["saves/",&path].join(""), serialized).expect("Unable to write file");
+//reads just the header of a save, None if it is too old to have metadata
+pub fn read_metadata(path: &Path) -> Result<Option<SaveMetadata>, SaveError>{
+    let mut file = File::open(path)?;
 
-        self.save_number+=1;
-        self.saves.push(path);
+    let mut start = [0; 12];
+    if file.read_exact(&mut start).is_err() || !start.starts_with(MAGIC) {
+        return Ok(None);
     }
+    let version = u32::from_le_bytes(start[4..8].try_into().unwrap());
+    if !(2..=SAVE_VERSION).contains(&version) {
+        return Ok(None);
+    }
+
+    //the length comes from the file, so a damaged one mustn't be trusted to size the buffer
+    let length = u32::from_le_bytes(start[8..12].try_into().unwrap()) as u64;
+    if length > file.metadata()?.len().saturating_sub(start.len() as u64) {
+        return Err(SaveError::Corrupted(io::Error::from(io::ErrorKind::UnexpectedEof)));
+    }
+    let mut metadata = vec![0; length as usize];
+    file.read_exact(&mut metadata).map_err(SaveError::Corrupted)?;
+    Ok(Some(rmp_serde::from_slice(&metadata)?))
+}
 
-    pub fn delete(&mut self, i:usize){
-        fs::remove_file(["saves/",self.saves.index(i)].join("")).unwrap();
-        self.saves.remove(i);
+//fills in what an older save lacks once it has been read, which for now is only a name from its seed for saves from before worlds were named
+//json (version 0) worlds have already been brought up to date by migrate_json, and versions 1 and 2 only differ in the header, so the version isn't needed yet
+fn migrate(save: &mut SimulationSave, _version: u32){
+    if save.name.is_empty() {
+        save.name = default_name(&save.sim_params);
     }
 }
+
+pub fn default_name(sim_params: &SimParams) -> String{
+    format!("World {}", sim_params.world.seed)
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct SimulationSave{
-    step: i32,
-    animals: Animals,
-    plants: Plants,
-    fruits: Fruits,
-    eggs: Eggs,
-    species_list: SpeciesList,
-    stats: Stats,
-    sim_params: SimParams,
-    rock_map: RockMap,
-    fruit_spawners: FruitSpawners,
-    plant_spawners: PlantSpawners,
-}
-
-impl SimulationSave{
-    pub fn open(self) -> (i32,Animals,Plants,Fruits,Eggs,SpeciesList,Stats,SimParams,RockMap,FruitSpawners,PlantSpawners){
-        (self.step,self.animals,self.plants,self.fruits,self.eggs,self.species_list,self.stats,self.sim_params,self.rock_map,self.fruit_spawners,self.plant_spawners)
-    }
+    #[serde(default)]
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) log: EventLog,
+    pub(crate) step: i32,
+    pub(crate) animals: Animals,
+    pub(crate) plants: Plants,
+    pub(crate) fruits: Fruits,
+    pub(crate) eggs: Eggs,
+    pub(crate) species_list: SpeciesList,
+    pub(crate) stats: Stats,
+    pub(crate) sim_params: SimParams,
+    pub(crate) rock_map: RockMap,
+    pub(crate) fruit_spawners: FruitSpawners,
+    pub(crate) plant_spawners: PlantSpawners,
+    //the collision grids and temperature map are only rebuilt every so often, so they are saved as they were last built
+    //saves without them rebuild them from the world as it is
+    #[serde(default)]
+    pub(crate) grids: Option<GridCells>,
+    #[serde(default)]
+    pub(crate) temperature: Option<TemperatureSettings>,
 }
\ No newline at end of file
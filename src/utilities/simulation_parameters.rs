@@ -1,6 +1,13 @@
+use std::fs;
+use std::io;
+use std::path::Path;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize,Deserialize,Clone)]
+//worlds are reproducible by default, a random seed has to be asked for
+pub const DEFAULT_SEED: u64 = 42;
+
+#[derive(Serialize,Deserialize,Clone,PartialEq)]
+#[serde(default)]
 pub struct SimParams{
     pub plants: PlantSettings,
     pub fruit: FruitSettings,
@@ -9,14 +16,18 @@ pub struct SimParams{
     pub simulation: SimulationSettings,
     pub world: WorldSettings,
     pub temp: TemperatureSettings,
-    pub save_id: usize,
+    //the save picked in the load menu, which isn't part of the world
+    #[serde(skip)]
+    pub save_file: String,
     pub autosave: i32,
+    //how many autosaves are kept before the oldest is deleted
+    pub autosave_keep: usize,
 }
-#[derive(Serialize,Deserialize,Clone)]
+#[derive(Serialize,Deserialize,Clone,PartialEq)]
 pub struct SimulationSettings {
     pub steps_per_frame: u8,
 }
-#[derive(Serialize,Deserialize,Clone)]
+#[derive(Serialize,Deserialize,Clone,PartialEq)]
 pub struct BuildSettings {
     pub pen_size: i32,
     pub pen: Pen,
@@ -28,7 +39,7 @@ pub enum Pen{
     PlantGenerator,
     FruitGenerator,
 }
-#[derive(Serialize,Deserialize,Clone)]
+#[derive(Serialize,Deserialize,Clone,PartialEq)]
 pub struct PlantSettings {
     pub global_spawn_rate: u8,
     pub spawn_rate: u8,
@@ -36,7 +47,7 @@ pub struct PlantSettings {
     pub energy: f32,
     pub protein: f32,
 }
-#[derive(Serialize,Deserialize,Clone)]
+#[derive(Serialize,Deserialize,Clone,PartialEq)]
 pub struct FruitSettings {
     pub global_spawn_rate: u8,
     pub spawn_rate: u8,
@@ -44,7 +55,7 @@ pub struct FruitSettings {
     pub energy: f32,
     pub protein: f32,
 }
-#[derive(Serialize,Deserialize,Clone)]
+#[derive(Serialize,Deserialize,Clone,PartialEq)]
 pub struct TemperatureSettings {
     pub spread: f32,
     pub smooth: u8,
@@ -52,10 +63,11 @@ pub struct TemperatureSettings {
     pub plant_spawner_temp: f32,
     pub fruit_spawner_temp: f32,
 }
-#[derive(Serialize,Deserialize,Clone)]
+#[derive(Serialize,Deserialize,Clone,PartialEq)]
 pub struct AnimalSettings{
     pub brain_mutation_rate: f32,
     pub brain_mutation_strength: f32,
+    pub structural_mutation_rate: f32,
     pub physical_mutation_rate: f32,
     pub physical_mutation_strength: f32,
     pub speciation_threshold: f32,
@@ -74,16 +86,18 @@ pub struct AnimalSettings{
     pub reproduction_time: f32,
     pub reproduction_protein_cost: f32,
     pub reproduction_energy_cost: f32,
+    pub sexual_reproduction: bool,
     pub lifespan: f32,
     pub temperature_sensitivity: f32
 }
-#[derive(Serialize,Deserialize,Clone)]
+#[derive(Serialize,Deserialize,Clone,PartialEq)]
 pub struct WorldSettings{
     pub width: f32,
     pub height: f32,
     pub plant_spawners: u8,
     pub fruit_spawners: u8,
     pub generate_terrain: bool,
+    pub seed: u64,
 }
 impl Default for SimParams{
     fn default() -> Self {
@@ -105,6 +119,7 @@ impl Default for SimParams{
             animals: AnimalSettings{
                 brain_mutation_rate: 6.0,
                 brain_mutation_strength: 10.,
+                structural_mutation_rate: 2.0,
                 physical_mutation_rate: 15.0,
                 physical_mutation_strength: 10.0,
                 speciation_threshold: 0.1,
@@ -123,6 +138,7 @@ impl Default for SimParams{
                 reproduction_time: 1.0,
                 reproduction_protein_cost: 1.0,
                 reproduction_energy_cost: 1.0,
+                sexual_reproduction: false,
                 lifespan: 1.0,
                 temperature_sensitivity: 60.0,
             },
@@ -139,6 +155,7 @@ impl Default for SimParams{
                 plant_spawners: 10,
                 fruit_spawners: 10,
                 generate_terrain: true,
+                seed: DEFAULT_SEED,
             },
             temp: TemperatureSettings {
                 spread: 0.97,
@@ -147,8 +164,37 @@ impl Default for SimParams{
                 plant_spawner_temp: 15.0,
                 fruit_spawner_temp: 45.0,
             },
-            save_id: 0,
+            save_file: String::new(),
             autosave: 300,
+            autosave_keep: 5,
         }
     }
-}
\ No newline at end of file
+}
+impl SimParams{
+    //config files only need to list the sections they change, the rest fall back to the defaults
+    pub fn load(path: &Path) -> io::Result<Self>{
+        let data = fs::read_to_string(path)?;
+        let mut sim_params: Self = serde_json::from_str(&data)?;
+        //the autosave interval divides the step count
+        sim_params.autosave = sim_params.autosave.max(1);
+        Ok(sim_params)
+    }
+    pub fn save(&self, path: &Path) -> io::Result<()>{
+        let serialized = serde_json::to_string_pretty(self)?;
+        fs::write(path, serialized)
+    }
+    //the build pen, speed and saving only change how the window runs a world, not how it plays out, so the event log leaves them at their defaults
+    pub fn without_window_settings(&self) -> Self{
+        self.clone().with_window_settings(&Self::default())
+    }
+    pub fn with_window_settings(self, window: &SimParams) -> Self{
+        Self{
+            build: window.build.clone(),
+            simulation: window.simulation.clone(),
+            save_file: window.save_file.clone(),
+            autosave: window.autosave,
+            autosave_keep: window.autosave_keep,
+            ..self
+        }
+    }
+}
@@ -6,5 +6,7 @@ pub enum State{
     LoadSave,
     NewSim,
     SaveSim,
+    ResumeAutosave,
+    ReplaySave,
     Exit,
 }
\ No newline at end of file
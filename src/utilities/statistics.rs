@@ -1,10 +1,17 @@
+use std::collections::BTreeMap;
 use serde::{Deserialize, Serialize};
 use sysinfo::System;
-use crate::environment::animal::Animal;
+use crate::environment::animal::{Animal, Death, DeathCause};
+use crate::environment::energy::{add_link, EnergyFlows, FeedingLink, FlowKind};
+use crate::environment::genome::GENES;
 #[derive(Serialize,Deserialize,Clone)]
 pub struct Stats{
     pub populations: Populations,
     pub distributions: Distributions,
+    #[serde(default)]
+    pub life_history: LifeHistory,
+    #[serde(default)]
+    pub energy: EnergyStats,
     pub fps: usize,
     pub used_mem: u64,
     pub tot_mem: u64,
@@ -23,6 +30,75 @@ pub struct Distributions{
     pub plant_vision: Vec<f64>,
     pub animal_vision: Vec<f64>,
     pub rock_vision: Vec<f64>,
+    #[serde(default)]
+    pub ideal_temp: Vec<f64>,
+    #[serde(default)]
+    pub temp_tolerance: Vec<f64>,
+    #[serde(default)]
+    pub offspring_investment: Vec<f64>,
+    #[serde(default)]
+    pub generation: Vec<f64>,
+    //(sample, every histogram in the order given by histograms()) taken every HISTORY_INTERVAL samples
+    #[serde(default)]
+    pub history: Vec<(f64,Vec<Vec<f64>>)>,
+}
+//a histogram along with where its bins sit, bin i is centred on start + i * width
+pub struct Histogram<'a>{
+    pub name: &'static str,
+    pub start: f64,
+    pub width: f64,
+    pub counts: &'a [f64],
+}
+//the distributions are kept over time at a lower rate than the population graphs to bound memory use
+pub const HISTORY_INTERVAL: usize = 60;
+//generations are grouped in fives, with the last bin holding everything later
+pub const GENERATION_BIN: usize = 5;
+pub const GENERATION_BINS: usize = 21;
+
+impl Distributions{
+    pub fn histograms(&self) -> Vec<Histogram<'_>>{
+        vec![
+            Histogram{ name: "Diet", start: 0., width: 0.1, counts: &self.diet },
+            Histogram{ name: "Speed", start: 0., width: 0.1, counts: &self.speed },
+            Histogram{ name: "Size", start: 0., width: 0.1, counts: &self.size },
+            Histogram{ name: "Attack", start: 0., width: 1., counts: &self.attack },
+            Histogram{ name: "Fruit vision", start: 0., width: 1., counts: &self.fruit_vision },
+            Histogram{ name: "Plant vision", start: 0., width: 1., counts: &self.plant_vision },
+            Histogram{ name: "Animal vision", start: 0., width: 1., counts: &self.animal_vision },
+            Histogram{ name: "Rock vision", start: 0., width: 1., counts: &self.rock_vision },
+            Histogram{ name: "Ideal temp", start: 0., width: 5., counts: &self.ideal_temp },
+            Histogram{ name: "Temp tolerance", start: 0., width: 1., counts: &self.temp_tolerance },
+            Histogram{ name: "Offspring investment", start: 0., width: 1., counts: &self.offspring_investment },
+            Histogram{ name: "Generation", start: 0., width: GENERATION_BIN as f64, counts: &self.generation },
+        ]
+    }
+    fn clear(&mut self){
+        self.diet = vec![0.;11];
+        self.speed = vec![0.;11];
+        self.size = vec![0.;11];
+        self.attack = vec![0.;11];
+        self.fruit_vision = vec![0.;13];
+        self.plant_vision = vec![0.;13];
+        self.animal_vision = vec![0.;13];
+        self.rock_vision = vec![0.;13];
+        self.ideal_temp = vec![0.;8];
+        self.temp_tolerance = vec![0.;16];
+        self.offspring_investment = vec![0.;11];
+        self.generation = vec![0.;GENERATION_BINS];
+    }
+    fn add(&mut self, animal: &Animal){
+        let bin = |value: f32, bins: usize| (value.round().max(0.) as usize).min(bins - 1);
+
+        self.attack[bin(animal.combat_stats.attack, 11)] += 1.;
+        self.fruit_vision[bin(animal.senses.fruit_vision, 13)] += 1.;
+        self.plant_vision[bin(animal.senses.plant_vision, 13)] += 1.;
+        self.animal_vision[bin(animal.senses.animal_vision, 13)] += 1.;
+        self.rock_vision[bin(animal.senses.rock_vision, 13)] += 1.;
+        self.ideal_temp[bin(animal.ideal_temp / 5., 8)] += 1.;
+        self.temp_tolerance[bin(animal.temp_tolerance, 16)] += 1.;
+        self.offspring_investment[bin(animal.reproduction_stats.offspring_investment, 11)] += 1.;
+        self.generation[(animal.generation / GENERATION_BIN).min(GENERATION_BINS - 1)] += 1.;
+    }
 }
 #[derive(Serialize,Deserialize,Clone)]
 pub struct Populations{
@@ -34,6 +110,115 @@ pub struct Populations{
     pub carnivores: Vec<[f64;2]>,
     pub average_speed: Vec<[f64;2]>,
     pub average_size: Vec<[f64;2]>,
+    //one series per entry in GENES
+    #[serde(default)]
+    pub average_genes: Vec<Vec<[f64;2]>>,
+    #[serde(default)]
+    pub species: BTreeMap<usize,SpeciesSeries>,
+    #[serde(default)]
+    pub diversity: Diversity,
+}
+//diversity of the living animals, the unspeciated founders counting as one more species
+#[derive(Default,Serialize,Deserialize,Clone)]
+pub struct Diversity{
+    //number of species with living members
+    pub richness: Vec<[f64;2]>,
+    //-sum(p ln p) over the share p of each species
+    pub shannon: Vec<[f64;2]>,
+    //chance that two animals picked at random belong to different species
+    pub simpson: Vec<[f64;2]>,
+    //total variance of the genome, with each gene scaled to its range
+    pub disparity: Vec<[f64;2]>,
+}
+#[derive(Default,Serialize,Deserialize,Clone)]
+pub struct SpeciesSeries{
+    pub population: Vec<[f64;2]>,
+    //mean of every entry in GENES, one per population sample
+    pub mean_genes: Vec<Vec<f32>>,
+}
+//age at death is grouped into half hour bins, with the last bin holding everything older
+pub const AGE_BIN_MINUTES: f32 = 30.;
+pub const AGE_BINS: usize = 21;
+//offspring per individual, with the last bin holding everything above
+pub const OFFSPRING_BINS: usize = 11;
+
+#[derive(Default,Serialize,Deserialize,Clone)]
+pub struct LifeHistory{
+    //deaths since the previous sample, one series per entry in DeathCause::ALL
+    pub deaths: Vec<Vec<[f64;2]>>,
+    pub age_at_death: Vec<f64>,
+    pub offspring: Vec<f64>,
+    pub total_deaths: Vec<usize>,
+    //deaths counted since the last sample was taken
+    pending: Vec<usize>,
+}
+impl LifeHistory{
+    fn record(&mut self, deaths: Vec<Death>){
+        self.age_at_death.resize(AGE_BINS, 0.);
+        self.offspring.resize(OFFSPRING_BINS, 0.);
+        self.total_deaths.resize(DeathCause::ALL.len(), 0);
+        self.pending.resize(DeathCause::ALL.len(), 0);
+
+        deaths.into_iter().for_each(|death|{
+            let cause = DeathCause::ALL.iter().position(|cause| *cause == death.cause).unwrap();
+            self.pending[cause] += 1;
+            self.total_deaths[cause] += 1;
+
+            let age = ((death.age / 60. / AGE_BIN_MINUTES) as usize).min(AGE_BINS - 1);
+            self.age_at_death[age] += 1.;
+            self.offspring[death.offspring.min(OFFSPRING_BINS - 1)] += 1.;
+        });
+    }
+    fn sample(&mut self, step: f64){
+        self.deaths.resize(DeathCause::ALL.len(), vec![]);
+        self.deaths.iter_mut().zip(self.pending.iter_mut()).for_each(|(series, pending)|{
+            series.push([step, *pending as f64]);
+            *pending = 0;
+        });
+    }
+    pub fn mean_age_at_death(&self) -> f32{
+        let deaths = self.age_at_death.iter().sum::<f64>();
+        let total = self.age_at_death.iter().enumerate().map(|(i, count)| (i as f64 + 0.5) * AGE_BIN_MINUTES as f64 * count).sum::<f64>();
+        (total / deaths.max(1.)) as f32
+    }
+}
+
+#[derive(Default,Serialize,Deserialize,Clone)]
+pub struct EnergyStats{
+    //mean flow per step since the previous sample, one series per entry in FlowKind::ALL
+    pub energy: Vec<Vec<[f64;2]>>,
+    pub protein: Vec<Vec<[f64;2]>>,
+    //energy held by living animals
+    pub stock: Vec<[f64;2]>,
+    //change in the stock per step that the flows don't explain, zero while energy is conserved
+    pub imbalance: Vec<[f64;2]>,
+    //energy each species has absorbed from each of its foods
+    pub food_web: Vec<FeedingLink>,
+    //flows collected since the last sample was taken
+    pending: EnergyFlows,
+    last_stock: Option<f64>,
+}
+impl EnergyStats{
+    fn sample(&mut self, step: f64, animals: &[Animal]){
+        let pending = std::mem::take(&mut self.pending);
+        let steps = pending.steps.max(1) as f64;
+
+        self.energy.resize(FlowKind::ALL.len(), vec![]);
+        self.protein.resize(FlowKind::ALL.len(), vec![]);
+        FlowKind::ALL.iter().enumerate().for_each(|(i, kind)|{
+            self.energy[i].push([step, pending.get(*kind).energy / steps]);
+            self.protein[i].push([step, pending.get(*kind).protein / steps]);
+        });
+
+        let stock = animals.iter().map(|animal| animal.resources.energy as f64).fold(0., |sum, energy| sum + energy);
+        if let Some(last_stock) = self.last_stock {
+            self.imbalance.push([step, (stock - last_stock - pending.net_energy()) / steps]);
+        }
+        self.stock.push([step, stock]);
+        self.last_stock = Some(stock);
+
+        pending.links.into_iter().for_each(|link| add_link(&mut self.food_web, link.eater, link.food, link.energy));
+    }
 }
 
 impl Default for Stats{
@@ -48,6 +233,9 @@ impl Default for Stats{
                 carnivores: vec![],
                 average_speed: vec![],
                 average_size: vec![],
+                average_genes: vec![vec![]; GENES.len()],
+                species: BTreeMap::new(),
+                diversity: Diversity::default(),
             },
             distributions: Distributions{
                 diet: vec![0.;11],
@@ -58,7 +246,14 @@ impl Default for Stats{
                 plant_vision: vec![0.;13],
                 animal_vision: vec![0.;13],
                 rock_vision: vec![0.;13],
+                ideal_temp: vec![0.;8],
+                temp_tolerance: vec![0.;16],
+                offspring_investment: vec![0.;11],
+                generation: vec![0.;GENERATION_BINS],
+                history: vec![],
             },
+            life_history: LifeHistory::default(),
+            energy: EnergyStats::default(),
             fps: 0,
             used_mem: 0,
             tot_mem: 0,
@@ -79,6 +274,12 @@ impl Stats{
         self.populations.carnivores = vec![];
         self.populations.average_size = vec![];
         self.populations.average_speed = vec![];
+        self.populations.average_genes = vec![vec![]; GENES.len()];
+        self.populations.species = BTreeMap::new();
+        self.populations.diversity = Diversity::default();
+        self.life_history = LifeHistory::default();
+        self.energy = EnergyStats::default();
+        self.distributions.history = vec![];
     }
     pub fn update_diagnostics(&mut self, frames: usize,system: &mut System){
         system.refresh_memory();
@@ -89,15 +290,16 @@ impl Stats{
         self.used_mem = system.used_memory();
         self.fps = frames;
     }
-    pub fn update_graphs(&mut self, animal_population: usize, fruit_population: usize, plant_population: usize, animals: &[Animal]){
+    pub fn update_graphs(&mut self, animal_population: usize, fruit_population: usize, plant_population: usize, animals: &[Animal], deaths: Vec<Death>, flows: EnergyFlows){
+        self.life_history.record(deaths);
+        self.energy.pending.merge(flows);
+
         if self.step % self.step_time == 0 {
             self.populations.animals.push([self.step as f64, animal_population as f64]);
             self.populations.plants.push([self.step as f64, plant_population as f64]);
             self.populations.fruit.push([self.step as f64, fruit_population as f64]);
 
-            self.distributions.diet = vec![0.;11];
-            self.distributions.speed = vec![0.;11];
-            self.distributions.size = vec![0.;11];
+            self.distributions.clear();
 
             let (mut herb,mut omni,mut carn) = (0.,0.,0.);
             let (mut slow,mut moderate,mut fast) = (0.,0.,0.);
@@ -117,8 +319,15 @@ impl Stats{
                 let size = (animal.body.scale - 0.08)/0.42;
                 avg_size += size;
                 update_stats((size*10.).round() as usize,&mut small,&mut medium,&mut large,&mut self.distributions.size);
+
+                self.distributions.add(animal);
             });
 
+            if self.step.is_multiple_of(self.step_time * HISTORY_INTERVAL) {
+                let histograms = self.distributions.histograms().iter().map(|histogram| histogram.counts.to_vec()).collect();
+                self.distributions.history.push((self.step as f64, histograms));
+            }
+
             self.populations.herbivores.push([self.step as f64, herb]);
             self.populations.omnivores.push([self.step as f64, omni]);
             self.populations.carnivores.push([self.step as f64, carn]);
@@ -129,9 +338,66 @@ impl Stats{
             }
             self.populations.average_speed.push([self.step as f64, avg_speed as f64]);
             self.populations.average_size.push([self.step as f64,avg_size as f64]);
+
+            let mut avg_genes = vec![0.; GENES.len()];
+            animals.iter().for_each(|animal| animal.genome.iter().enumerate().for_each(|(i, (_, value))| avg_genes[i] += value));
+            self.populations.average_genes.resize(GENES.len(), vec![]);
+            self.populations.average_genes.iter_mut().zip(avg_genes).for_each(|(series, total)|{
+                series.push([self.step as f64, (total / animals.len().max(1) as f32) as f64]);
+            });
+
+            self.update_species(animals);
+            self.update_diversity(animals);
+            self.life_history.sample(self.step as f64);
+            self.energy.sample(self.step as f64, animals);
         }
         self.step+=1
     }
+    fn update_diversity(&mut self, animals: &[Animal]){
+        let step = self.step as f64;
+        let total = animals.len().max(1) as f64;
+
+        let mut counts: BTreeMap<usize,usize> = BTreeMap::new();
+        animals.iter().for_each(|animal| *counts.entry(animal.species_id).or_default() += 1);
+        let shares = counts.values().map(|count| *count as f64 / total);
+
+        let shannon = shares.clone().map(|p| p * (1. / p).ln()).fold(0., |sum, term| sum + term);
+        let simpson = if animals.is_empty() { 0. } else { 1. - shares.map(|p| p * p).sum::<f64>() };
+
+        let scaled = |animal: &Animal| animal.genome.iter().map(|(gene, value)| ((value - gene.min) / (gene.max - gene.min)) as f64).collect::<Vec<f64>>();
+        let mut means = vec![0.; GENES.len()];
+        animals.iter().for_each(|animal| means.iter_mut().zip(scaled(animal)).for_each(|(mean, value)| *mean += value / total));
+        let disparity = animals.iter().map(|animal| scaled(animal).iter().zip(means.iter()).map(|(value, mean)| (value - mean).powi(2)).sum::<f64>()).fold(0., |sum, variance| sum + variance) / total;
+
+        let diversity = &mut self.populations.diversity;
+        diversity.richness.push([step, counts.len() as f64]);
+        diversity.shannon.push([step, shannon]);
+        diversity.simpson.push([step, simpson]);
+        diversity.disparity.push([step, disparity]);
+    }
+    fn update_species(&mut self, animals: &[Animal]){
+        let mut totals: BTreeMap<usize,(usize,Vec<f32>)> = BTreeMap::new();
+        animals.iter().for_each(|animal|{
+            let (count, genes) = totals.entry(animal.species_id).or_insert_with(|| (0, vec![0.; GENES.len()]));
+            *count += 1;
+            genes.iter_mut().zip(animal.genome.iter()).for_each(|(total, (_, value))| *total += value);
+        });
+
+        //species that have just died out get a final zero so their series ends at the right time
+        let step = self.step as f64;
+        self.populations.species.iter_mut().for_each(|(id, series)|{
+            if !totals.contains_key(id) && series.population.last().is_some_and(|last| last[1] > 0.) {
+                series.population.push([step, 0.]);
+                series.mean_genes.push(vec![0.; GENES.len()]);
+            }
+        });
+
+        totals.into_iter().for_each(|(id, (count, genes))|{
+            let series = self.populations.species.entry(id).or_default();
+            series.population.push([step, count as f64]);
+            series.mean_genes.push(genes.iter().map(|total| total / count as f32).collect());
+        });
+    }
 }
 
 fn update_stats(stat: usize,low: &mut f64, medium: &mut f64, high: &mut f64, distribution: &mut Vec<f64>){
@@ -0,0 +1,95 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, Sender};
+use crate::utilities::save_system::{decode, Encoded, SaveError, SaveSystem};
+use crate::world::World;
+
+//a world compressed the same way as a save, without its event log or stats
+pub struct Snapshot{
+    pub step: i32,
+    //how long the event log was when the snapshot was taken
+    log_len: usize,
+    data: Vec<u8>,
+}
+
+//the most recent snapshots of the running world, taken every interval steps, that it can be rewound to
+pub struct Timeline{
+    snapshots: VecDeque<Snapshot>,
+    interval: i32,
+    pub capacity: usize,
+    //snapshots being encoded on the save thread, in the order they were taken
+    pending: VecDeque<(i32, usize)>,
+    encoded: (Sender<Encoded>, Receiver<Encoded>),
+}
+
+impl Default for Timeline{
+    fn default() -> Self {
+        Self{
+            snapshots: VecDeque::new(),
+            interval: 3600,
+            capacity: 30,
+            pending: VecDeque::new(),
+            encoded: mpsc::channel(),
+        }
+    }
+}
+
+impl Timeline{
+    pub fn interval(&self) -> i32{
+        self.interval
+    }
+    pub fn set_interval(&mut self, interval: i32){
+        self.interval = interval.max(1);
+    }
+
+    //keeps any snapshots the save thread has finished and hands it a new one if the world is on an interval step
+    pub fn update(&mut self, world: &World, save_system: &SaveSystem) -> Result<(), SaveError>{
+        self.receive()?;
+
+        let step = world.current_step();
+        if step % self.interval != 0 || self.snapshots.iter().any(|snapshot| snapshot.step == step) || self.pending.iter().any(|(pending, _)| *pending == step) {
+            return Ok(());
+        }
+
+        save_system.queue_encode(world.snapshot(), self.encoded.0.clone())?;
+        self.pending.push_back((step, world.log().len()));
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<(), SaveError>{
+        while let Ok(encoded) = self.encoded.1.try_recv() {
+            let Some((step, log_len)) = self.pending.pop_front() else { break };
+            self.snapshots.push_back(Snapshot{ step, log_len, data: encoded? });
+            while self.snapshots.len() > self.capacity.max(1) {
+                self.snapshots.pop_front();
+            }
+        }
+        Ok(())
+    }
+
+    //rewinds the world to snapshot i, the snapshots after it are dropped as the event log no longer leads to them
+    pub fn restore(&mut self, i: usize, world: &mut World) -> Result<(), SaveError>{
+        let snapshot = self.snapshots.get(i).ok_or(SaveError::NoSnapshot(i))?;
+        world.restore(decode(&snapshot.data)?, snapshot.log_len);
+
+        self.snapshots.truncate(i + 1);
+        self.forget_pending();
+        Ok(())
+    }
+
+    pub fn snapshots(&self) -> &VecDeque<Snapshot>{
+        &self.snapshots
+    }
+    //bytes held by all the snapshots
+    pub fn size(&self) -> usize{
+        self.snapshots.iter().map(|snapshot| snapshot.data.len()).sum()
+    }
+    pub fn clear(&mut self){
+        self.snapshots.clear();
+        self.forget_pending();
+    }
+    //snapshots still being encoded are sent to a channel that is dropped here
+    fn forget_pending(&mut self){
+        self.pending.clear();
+        self.encoded = mpsc::channel();
+    }
+}
@@ -0,0 +1,353 @@
+use crate::environment::animal::{Animal, Animals};
+use crate::environment::collisions::{Collisions, DIV};
+use crate::environment::eggs::Eggs;
+use crate::environment::fruit::{Fruits, FruitSpawners};
+use crate::environment::plants::{Plants, PlantSpawners};
+use crate::environment::rocks::RockMap;
+use crate::environment::species::SpeciesList;
+use crate::environment::temperature::TemperatureMap;
+use crate::utilities::event_log::{Event, EventLog, LoggedEvent, Replay};
+use crate::utilities::random::{Stream, stream};
+use crate::utilities::save_system::{default_name, SaveError, SimulationSave};
+use crate::utilities::simulation_parameters::{BuildSettings, Pen, SimParams, TemperatureSettings};
+use crate::utilities::statistics::Stats;
+
+pub struct World{
+    name: String,
+    step: i32,
+    animals: Animals,
+    plants: Plants,
+    fruit: Fruits,
+    eggs: Eggs,
+    species_list: SpeciesList,
+    stats: Stats,
+    sim_params: SimParams,
+    collisions: Collisions,
+    rocks: RockMap,
+    plant_spawners: PlantSpawners,
+    fruit_spawners: FruitSpawners,
+    temp_map: TemperatureMap,
+    //the settings the temperature map was last built with
+    temp_settings: TemperatureSettings,
+    log: EventLog,
+    replay: Option<Replay>,
+}
+
+impl World{
+    pub fn new(sim_params: SimParams) -> Self{
+        let collisions = Collisions::new(&sim_params);
+
+        let mut rocks = RockMap::new(collisions.cells_height);
+        let mut plant_spawners = PlantSpawners{ bodies: vec![] };
+        let mut fruit_spawners = FruitSpawners{ bodies: vec![] };
+
+        let seed = sim_params.world.seed;
+
+        if sim_params.world.generate_terrain{
+            rocks.randomise(&mut stream(seed, Stream::Terrain));
+        }
+
+        let mut spawner_rng = stream(seed, Stream::Spawners);
+        fruit_spawners.random(&sim_params, &mut spawner_rng);
+        plant_spawners.random(&sim_params, &mut spawner_rng);
+
+        let mut world = Self{
+            name: default_name(&sim_params),
+            step: 0,
+            animals: Animals::genesis(seed),
+            plants: Plants::genesis(seed),
+            fruit: Fruits::genesis(seed),
+            eggs: Eggs::default(),
+            species_list: SpeciesList::default(),
+            stats: Stats::default(),
+            temp_map: TemperatureMap::new(collisions.cells_height),
+            temp_settings: sim_params.temp.clone(),
+            sim_params,
+            collisions,
+            rocks,
+            plant_spawners,
+            fruit_spawners,
+            log: EventLog::default(),
+            replay: None,
+        };
+        world.update_temperature();
+        world.record(Event::Start(world.sim_params.without_window_settings()));
+        world
+    }
+
+    pub fn from_save(save: SimulationSave) -> Self{
+        let mut collisions = Collisions::new(&save.sim_params);
+        match &save.grids {
+            Some(grids) => collisions.restore(grids),
+            None => {
+                collisions.update_animal_grid(save.animals.instances().as_slice());
+                collisions.update_plant_grid(save.plants.instances());
+                collisions.update_fruit_grid(save.fruits.instances());
+            }
+        }
+
+        let mut world = Self{
+            name: save.name,
+            step: save.step,
+            animals: save.animals,
+            plants: save.plants,
+            fruit: save.fruits,
+            eggs: save.eggs,
+            species_list: save.species_list,
+            stats: save.stats,
+            temp_settings: save.temperature.unwrap_or_else(|| save.sim_params.temp.clone()),
+            sim_params: save.sim_params,
+            temp_map: TemperatureMap::new(collisions.cells_height),
+            collisions,
+            rocks: save.rock_map,
+            plant_spawners: save.plant_spawners,
+            fruit_spawners: save.fruit_spawners,
+            log: save.log,
+            replay: None,
+        };
+        world.update_temperature();
+        world
+    }
+
+    //a new world made with the parameters the save started from, which is fed the same inputs at the same steps and checks it reproduces the same outcomes
+    pub fn replay(save: SimulationSave) -> Result<Self, SaveError>{
+        let sim_params = save.log.start().cloned().ok_or(SaveError::NoEventLog)?;
+
+        let mut world = Self::new(sim_params.with_window_settings(&save.sim_params));
+        world.name = save.name;
+        world.replay = Some(Replay::new(save.log, save.step));
+        Ok(world)
+    }
+
+    //a copy of the world exactly as it is, dead bodies included, so that saving never changes how the run plays out
+    pub fn to_save(&self) -> SimulationSave{
+        SimulationSave{
+            log: self.log.clone(),
+            stats: self.stats.clone(),
+            ..self.snapshot()
+        }
+    }
+
+    //a save without the event log and stats, which only grow, for rewinding the world to with restore
+    pub fn snapshot(&self) -> SimulationSave{
+        SimulationSave{
+            name: self.name.clone(),
+            log: EventLog::default(),
+            step: self.step,
+            animals: self.animals.clone(),
+            plants: self.plants.clone(),
+            fruits: self.fruit.clone(),
+            eggs: self.eggs.clone(),
+            species_list: self.species_list.clone(),
+            stats: Stats::default(),
+            sim_params: self.sim_params.clone(),
+            rock_map: self.rocks.clone(),
+            fruit_spawners: self.fruit_spawners.clone(),
+            plant_spawners: self.plant_spawners.clone(),
+            grids: Some(self.collisions.cells()),
+            temperature: Some(self.temp_settings.clone()),
+        }
+    }
+
+    //rewinds the world to a snapshot, cutting the log back to the length it had then and keeping the stats as they are
+    pub fn restore(&mut self, snapshot: SimulationSave, log_len: usize){
+        let mut log = std::mem::take(&mut self.log);
+        log.truncate(log_len);
+        let stats = std::mem::take(&mut self.stats);
+
+        *self = Self{
+            log,
+            stats,
+            ..Self::from_save(snapshot)
+        };
+        //the kept stats already hold the deaths and flows the snapshot was still waiting to hand over
+        self.animals.take_deaths();
+        self.animals.take_flows();
+    }
+
+    pub fn step(&mut self){
+        self.replay_inputs();
+        self.log_params();
+
+        let first_birth = self.animals.next_id();
+        let first_death = self.animals.deaths().len();
+        let first_species = self.species_list.count();
+
+        self.species_list.set_step(self.step);
+
+        if self.step % 3600 == 0 {
+            for _ in 0..self.sim_params.plants.spawn_rate {
+                self.plant_spawners.spawn(&mut self.plants, &self.rocks, &self.collisions, &self.sim_params);
+            }
+            for _ in 0..self.sim_params.fruit.spawn_rate {
+                self.fruit_spawners.spawn(&mut self.fruit, &self.rocks, &self.collisions, &self.sim_params);
+            }
+
+            for _ in 0..self.sim_params.plants.global_spawn_rate {
+                self.plants.spawn_random(&self.rocks, &self.collisions, &self.sim_params);
+            }
+            for _ in 0..self.sim_params.fruit.global_spawn_rate {
+                self.fruit.spawn_random(&self.rocks, &self.collisions, &self.sim_params);
+            }
+
+            if self.animals.count() < 40 {
+                for _ in 0..20{
+                    self.animals.spawn(&self.sim_params);
+                }
+            }
+        }
+
+        if self.step % 6 == 0 {
+            self.animals.kill();
+            self.plants.kill();
+            self.fruit.kill();
+            self.update_grids();
+
+            if self.step % 60 == 0 {
+                self.species_list.census(self.animals.animals.iter().chain(self.eggs.eggs.iter().map(|egg| &egg.animal)));
+            }
+        }
+
+        self.collisions.handle_collisions(&mut self.animals, &mut self.plants, &mut self.fruit, &self.sim_params);
+        self.eggs.update(&mut self.animals);
+        self.animals.update(&mut self.plants, &mut self.fruit, &mut self.eggs, &mut self.sim_params, &self.collisions, &mut self.species_list, &self.rocks, &self.temp_map);
+
+        self.log_outcomes(first_birth, first_death, first_species);
+
+        self.step += 1;
+
+        let produced = self.log.len();
+        if let Some(replay) = self.replay.as_mut().filter(|replay| replay.end_step == self.step) {
+            replay.finish(produced);
+        }
+    }
+
+    fn record(&mut self, event: Event){
+        let logged = LoggedEvent{ step: self.step, event };
+        if let Some(replay) = &mut self.replay {
+            replay.check(self.log.len(), &logged);
+        }
+        self.log.push(logged.step, logged.event);
+    }
+
+    //parameters changed through the gui are picked up the next time the world is stepped or painted
+    fn log_params(&mut self){
+        let sim_params = self.sim_params.without_window_settings();
+        if self.log.params() != Some(&sim_params) {
+            self.record(Event::Params(sim_params));
+        }
+    }
+
+    fn log_outcomes(&mut self, first_birth: usize, first_death: usize, first_species: usize){
+        for id in first_birth..self.animals.next_id() {
+            let [parent, mate] = self.animals.lineage().parents(id);
+            self.record(Event::Birth{ id, parent, mate });
+        }
+        let deaths: Vec<Event> = self.animals.deaths().get(first_death..).unwrap_or_default().iter().map(|death| Event::Death{ id: death.id, cause: death.cause }).collect();
+        deaths.into_iter().for_each(|event| self.record(event));
+        for species in first_species + 1..=self.species_list.count() {
+            let parent = self.species_list.get(species).map(|species| species.parent).unwrap_or_default();
+            self.record(Event::Speciation{ species, parent });
+        }
+    }
+
+    fn replay_inputs(&mut self){
+        let Some(replay) = &mut self.replay else { return };
+        for event in replay.inputs(self.step) {
+            match event {
+                Event::Params(sim_params) => {
+                    self.sim_params = sim_params.with_window_settings(&self.sim_params);
+                    self.log_params();
+                }
+                Event::Paint{ pos, place, build } => {
+                    let build = build.unwrap_or_else(|| self.sim_params.build.clone());
+                    self.paint_with(pos, place, &build);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn record_stats(&mut self){
+        let deaths = self.animals.take_deaths();
+        let flows = self.animals.take_flows();
+        self.stats.update_graphs(self.animals.count(), self.fruit.count(), self.plants.count(), &self.animals.animals, deaths, flows);
+    }
+
+    //applies the current build pen at a world position, placing when true and erasing when false
+    pub fn paint(&mut self, pos: [f32;2], place: bool) -> bool{
+        let build = self.sim_params.build.clone();
+        self.paint_with(pos, place, &build)
+    }
+    fn paint_with(&mut self, pos: [f32;2], place: bool, build: &BuildSettings) -> bool{
+        self.log_params();
+
+        let update = match (&build.pen, place) {
+            (Pen::Rock, true) => self.rocks.set(1, pos, build.pen_size),
+            (Pen::Rock, false) => self.rocks.set(0, pos, build.pen_size),
+            (Pen::PlantGenerator, true) => self.plant_spawners.place(pos, &self.sim_params),
+            (Pen::PlantGenerator, false) => self.plant_spawners.remove(pos),
+            (Pen::FruitGenerator, true) => self.fruit_spawners.place(pos, &self.sim_params),
+            (Pen::FruitGenerator, false) => self.fruit_spawners.remove(pos),
+            (Pen::None, _) => false,
+        };
+
+        if update {
+            if place {
+                self.plants.remove_plants_in_walls(&self.rocks);
+                self.fruit.remove_plants_in_walls(&self.rocks);
+
+                self.collisions.update_plant_grid(self.plants.instances());
+                self.collisions.update_fruit_grid(self.fruit.instances());
+            }
+
+            self.temp_map.clear();
+            self.temp_settings = self.sim_params.temp.clone();
+            self.update_temperature();
+
+            self.record(Event::Paint{ pos, place, build: Some(build.clone()) });
+        }
+        update
+    }
+
+    pub fn animal_at(&self, pos: [f32;2]) -> Option<&Animal>{
+        if pos[0] > 0. && pos[0] < self.sim_params.world.width && pos[1] > 0. && pos[1] < self.sim_params.world.height {
+            self.collisions.animals_grid[(pos[0] * DIV) as usize * self.collisions.cells_height + (pos[1] * DIV) as usize].object_ids.last().map(|i| &self.animals.animals[*i])
+        }
+        else {
+            None
+        }
+    }
+
+    fn update_grids(&mut self){
+        self.collisions.update_animal_grid(self.animals.instances().as_slice());
+        self.collisions.update_plant_grid(self.plants.instances());
+        self.collisions.update_fruit_grid(self.fruit.instances());
+    }
+
+    fn update_temperature(&mut self){
+        self.temp_map.set(self.temp_settings.plant_spawner_temp, self.plant_spawners.instances());
+        self.temp_map.set(self.temp_settings.fruit_spawner_temp, self.fruit_spawners.instances());
+        self.temp_map.update(self.temp_settings.spread, self.temp_settings.smooth, &self.rocks.rocks);
+    }
+
+    pub fn current_step(&self) -> i32{ self.step }
+    pub fn name(&self) -> &str{ &self.name }
+    pub fn name_mut(&mut self) -> &mut String{ &mut self.name }
+    pub fn animals(&self) -> &Animals{ &self.animals }
+    pub fn plants(&self) -> &Plants{ &self.plants }
+    pub fn fruit(&self) -> &Fruits{ &self.fruit }
+    pub fn eggs(&self) -> &Eggs{ &self.eggs }
+    pub fn species_list(&self) -> &SpeciesList{ &self.species_list }
+    pub fn stats(&self) -> &Stats{ &self.stats }
+    pub fn stats_mut(&mut self) -> &mut Stats{ &mut self.stats }
+    pub fn sim_params(&self) -> &SimParams{ &self.sim_params }
+    pub fn sim_params_mut(&mut self) -> &mut SimParams{ &mut self.sim_params }
+    pub fn collisions(&self) -> &Collisions{ &self.collisions }
+    pub fn rocks(&self) -> &RockMap{ &self.rocks }
+    pub fn plant_spawners(&self) -> &PlantSpawners{ &self.plant_spawners }
+    pub fn fruit_spawners(&self) -> &FruitSpawners{ &self.fruit_spawners }
+    pub fn temp_map(&self) -> &TemperatureMap{ &self.temp_map }
+    pub fn log(&self) -> &EventLog{ &self.log }
+    pub fn replay_status(&self) -> Option<&Replay>{ self.replay.as_ref() }
+}
@@ -0,0 +1,64 @@
+use ecosystem_simulation::utilities::save_system::{decode, encode, SaveMetadata};
+use ecosystem_simulation::utilities::simulation_parameters::{SimParams, DEFAULT_SEED};
+use ecosystem_simulation::world::World;
+
+fn run(seed: u64, steps: i32) -> World{
+    let mut sim_params = SimParams::default();
+    sim_params.world.seed = seed;
+
+    let mut world = World::new(sim_params);
+    while world.current_step() < steps {
+        world.step();
+    }
+    world
+}
+
+fn positions(world: &World) -> Vec<[f32;2]>{
+    world.animals().animals.iter().map(|animal| animal.body.position).collect()
+}
+
+#[test]
+fn same_seed_gives_the_same_run(){
+    let a = run(7, 4000);
+    let b = run(7, 4000);
+
+    assert!(a.log().len() > 1);
+    assert!(a.log().events().eq(b.log().events()));
+    assert_eq!(positions(&a), positions(&b));
+}
+
+#[test]
+fn different_seeds_give_different_runs(){
+    let a = run(7, 600);
+    let b = run(8, 600);
+
+    assert_ne!(positions(&a), positions(&b));
+}
+
+#[test]
+fn default_seed_is_fixed(){
+    assert_eq!(SimParams::default().world.seed, DEFAULT_SEED);
+}
+
+//the collision grids are only rebuilt every 6 steps, so a save made between rebuilds has to bring back the grids as they were
+#[test]
+fn loading_a_save_carries_on_the_same(){
+    let mut live = run(7, 1187);
+    let save = live.to_save();
+
+    //an animal has changed cell since the grids were last built, so rebuilding them on load would give different grids
+    let mut without_grids = serde_json::to_value(&save).unwrap();
+    without_grids.as_object_mut().unwrap().remove("grids");
+    let rebuilt = World::from_save(decode(&serde_json::to_vec(&without_grids).unwrap()).unwrap());
+    assert_ne!(rebuilt.collisions().cells().animals, live.collisions().cells().animals);
+
+    let mut loaded = World::from_save(decode(&encode(&save, &SaveMetadata::new(&save)).unwrap()).unwrap());
+    assert_eq!(loaded.collisions().cells().animals, live.collisions().cells().animals);
+
+    while live.current_step() < 1800 {
+        live.step();
+        loaded.step();
+    }
+    assert!(live.log().events().eq(loaded.log().events()));
+    assert_eq!(positions(&live), positions(&loaded));
+}
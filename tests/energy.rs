@@ -0,0 +1,31 @@
+use ecosystem_simulation::environment::energy::FlowKind;
+use ecosystem_simulation::utilities::simulation_parameters::SimParams;
+use ecosystem_simulation::world::World;
+
+fn total(series: &[[f64;2]]) -> f64{
+    series.iter().map(|[_, value]| value).sum()
+}
+
+//the energy held by living animals only changes by what the flows account for, up to rounding
+#[test]
+fn flows_account_for_the_change_in_stock(){
+    let mut sim_params = SimParams::default();
+    sim_params.world.width = 40.;
+    sim_params.world.height = 40.;
+    let mut world = World::new(sim_params);
+    while world.current_step() < 30000 {
+        world.step();
+        if world.current_step() % 60 == 0 {
+            world.record_stats();
+        }
+    }
+
+    let energy = &world.stats().energy;
+    assert_eq!(energy.imbalance.len(), energy.stock.len() - 1);
+    for kind in [FlowKind::Herbivory, FlowKind::Predation, FlowKind::Prey, FlowKind::Metabolism, FlowKind::Spawned] {
+        assert!(total(&energy.energy[kind as usize]) > 0., "no {} flow", kind.name());
+    }
+    for [step, imbalance] in &energy.imbalance {
+        assert!(imbalance.abs() < 1e-3, "{} energy unaccounted for per step at {}", imbalance, step);
+    }
+}
@@ -0,0 +1,78 @@
+use ecosystem_simulation::environment::genome::Gene;
+use ecosystem_simulation::utilities::save_system::{decode, encode, SaveMetadata};
+use ecosystem_simulation::utilities::simulation_parameters::DEFAULT_SEED;
+use ecosystem_simulation::world::World;
+use serde_json::Value;
+
+//a json save written before the save format was versioned, 1800 steps into a 40 by 40 world with an egg and a species added
+const BASELINE_SAVE: &[u8] = include_bytes!("fixtures/baseline_save.json");
+
+fn baseline() -> Value{
+    serde_json::from_slice(BASELINE_SAVE).unwrap()
+}
+
+#[test]
+fn baseline_json_save_loads(){
+    let world = World::from_save(decode(BASELINE_SAVE).unwrap());
+    let old = baseline();
+
+    assert_eq!(world.current_step(), 1800);
+    assert_eq!(world.name(), "World 42");
+    assert_eq!(world.sim_params().world.seed, DEFAULT_SEED);
+    assert_eq!(world.sim_params().world.width, 40.);
+    assert_eq!(world.animals().count(), old["animals"]["animals"].as_array().unwrap().len());
+    assert_eq!(world.eggs().count(), 1);
+    assert_eq!(world.species_list().count(), 1);
+}
+
+#[test]
+fn traits_become_genes(){
+    let world = World::from_save(decode(BASELINE_SAVE).unwrap());
+    let old = baseline();
+
+    for (animal, old) in world.animals().animals.iter().zip(old["animals"]["animals"].as_array().unwrap()) {
+        assert_eq!(animal.genome[Gene::Speed], old["max_stats"]["speed"].as_f64().unwrap() as f32);
+        assert_eq!(animal.genome[Gene::Size], old["max_stats"]["size"].as_f64().unwrap() as f32);
+        assert_eq!(animal.genome[Gene::Attack], old["max_stats"]["attack"].as_f64().unwrap() as f32);
+        assert_eq!(animal.genome[Gene::Hue], old["hue"].as_f64().unwrap() as f32);
+        assert_eq!(animal.genome[Gene::IdealTemp], old["ideal_temp"].as_f64().unwrap() as f32);
+    }
+}
+
+#[test]
+fn weights_become_connections(){
+    let world = World::from_save(decode(BASELINE_SAVE).unwrap());
+    let old = baseline();
+
+    for (animal, old) in world.animals().animals.iter().zip(old["animals"]["animals"].as_array().unwrap()) {
+        assert_eq!(animal.brain.memory.len(), 2);
+        let layers = &animal.brain.network.layers;
+        for (layer, old_layer) in layers.iter().zip(old["brain"]["network"]["layers"].as_array().unwrap()).skip(1) {
+            for (neuron, old_neuron) in layer.neurons.iter().zip(old_layer["neurons"].as_array().unwrap()) {
+                assert_eq!(neuron.bias, old_neuron["bias"].as_f64().unwrap() as f32);
+                let weights: Vec<f32> = old_neuron["weights"].as_array().unwrap().iter().map(|weight| weight.as_f64().unwrap() as f32).collect();
+                let connected: Vec<f32> = neuron.connections.iter().take(weights.len()).map(|connection| connection.weight).collect();
+                assert_eq!(connected, weights);
+            }
+        }
+    }
+}
+
+//the migrated world carries on and can be saved again, in the current format or as json which passes through the migration untouched
+#[test]
+fn migrated_world_runs_and_saves(){
+    let mut world = World::from_save(decode(BASELINE_SAVE).unwrap());
+    let animals = world.animals().next_id();
+    while world.current_step() < 4000 {
+        world.step();
+    }
+    assert!(world.animals().next_id() > animals);
+
+    let save = world.to_save();
+    let reloaded = World::from_save(decode(&encode(&save, &SaveMetadata::new(&save)).unwrap()).unwrap());
+    assert_eq!(reloaded.current_step(), 4000);
+
+    let reloaded = World::from_save(decode(&serde_json::to_vec(&save).unwrap()).unwrap());
+    assert_eq!(reloaded.current_step(), 4000);
+    assert_eq!(reloaded.animals().count(), world.animals().count());
+}
@@ -0,0 +1,62 @@
+use ecosystem_simulation::utilities::event_log::Event;
+use ecosystem_simulation::utilities::simulation_parameters::{Pen, SimParams};
+use ecosystem_simulation::world::World;
+
+//a run with each kind of input, a parameter change and painting with two pens, as well as window settings changing along the way
+fn live_run() -> World{
+    let mut world = World::new(SimParams::default());
+    while world.current_step() < 2000 {
+        world.step();
+    }
+
+    world.sim_params_mut().animals.movement_speed = 1.5;
+    world.sim_params_mut().simulation.steps_per_frame = 10;
+    world.sim_params_mut().autosave = 1;
+    world.step();
+
+    world.sim_params_mut().build.pen = Pen::Rock;
+    world.sim_params_mut().build.pen_size = 3;
+    assert!(world.paint([30., 30.], true));
+    world.sim_params_mut().build.pen = Pen::PlantGenerator;
+    assert!(world.paint([60., 60.], true));
+    world.sim_params_mut().build.pen = Pen::None;
+
+    while world.current_step() < 4000 {
+        world.step();
+    }
+    world
+}
+
+fn positions(world: &World) -> Vec<[f32;2]>{
+    world.animals().animals.iter().map(|animal| animal.body.position).collect()
+}
+
+#[test]
+fn only_simulation_settings_are_logged(){
+    let world = live_run();
+    let params: Vec<&SimParams> = world.log().events().filter_map(|logged| match &logged.event {
+        Event::Params(sim_params) => Some(sim_params),
+        _ => None,
+    }).collect();
+
+    assert_eq!(params.len(), 1);
+    assert_eq!(params[0].animals.movement_speed, 1.5);
+    assert!(params[0].build == SimParams::default().build);
+    assert_eq!(params[0].simulation.steps_per_frame, SimParams::default().simulation.steps_per_frame);
+}
+
+#[test]
+fn replay_reaches_the_same_state(){
+    let live = live_run();
+    let mut replay = World::replay(live.to_save()).unwrap();
+    while replay.current_step() < live.current_step() {
+        replay.step();
+    }
+
+    assert!(replay.replay_status().unwrap().divergence.is_none());
+    assert!(replay.log().events().eq(live.log().events()));
+    assert_eq!(positions(&replay), positions(&live));
+    assert_eq!(replay.plant_spawners().bodies.len(), live.plant_spawners().bodies.len());
+    //the replay runs with the window settings the save was made with
+    assert_eq!(replay.sim_params().simulation.steps_per_frame, 10);
+}
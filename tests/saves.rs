@@ -0,0 +1,99 @@
+use std::fs;
+use std::path::PathBuf;
+use ecosystem_simulation::utilities::save_system::{encode, read_metadata, SaveError, SaveMetadata, SaveSystem, SAVE_VERSION};
+use ecosystem_simulation::utilities::simulation_parameters::SimParams;
+use ecosystem_simulation::world::World;
+
+//a fresh directory for each test, as they run in parallel
+fn test_dir(name: &str) -> PathBuf{
+    let dir = std::env::temp_dir().join("ecosystem_simulation_saves_test").join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn metadata_is_read_from_the_header(){
+    let dir = test_dir("metadata");
+    let save = World::new(SimParams::default()).to_save();
+    let path = dir.join("save.bin");
+    fs::write(&path, encode(&save, &SaveMetadata::new(&save)).unwrap()).unwrap();
+
+    let metadata = read_metadata(&path).unwrap().unwrap();
+    assert_eq!(metadata.seed, SimParams::default().world.seed);
+}
+
+#[test]
+fn damaged_metadata_length_is_rejected(){
+    let dir = test_dir("damaged");
+    let path = dir.join("save.bin");
+    let mut data = b"ESIM".to_vec();
+    data.extend_from_slice(&SAVE_VERSION.to_le_bytes());
+    data.extend_from_slice(&u32::MAX.to_le_bytes());
+    data.extend_from_slice(&[0; 16]);
+    fs::write(&path, data).unwrap();
+
+    assert!(matches!(read_metadata(&path), Err(SaveError::Corrupted(_))));
+}
+
+fn world(name: &str) -> World{
+    let mut world = World::new(SimParams::default());
+    *world.name_mut() = name.to_string();
+    world
+}
+
+//steps of the autosaves of the named world, newest first
+fn autosave_steps(save_system: &SaveSystem, name: &str) -> Vec<i32>{
+    save_system.autosaves().into_iter().filter_map(|entry| entry.metadata.as_ref()).filter(|metadata| metadata.name == name).map(|metadata| metadata.step).collect()
+}
+
+#[test]
+fn autosaves_are_rotated_per_world(){
+    let dir = test_dir("rotation");
+    let mut save_system = SaveSystem::new(&dir);
+    let (mut a, mut b) = (world("A"), world("B"));
+
+    for _ in 0..4 {
+        a.step();
+        save_system.autosave(a.to_save(), 2).unwrap();
+        save_system.flush();
+    }
+    let kept = save_system.saves.iter().find(|entry| entry.metadata.as_ref().is_some_and(|metadata| metadata.step == 4)).unwrap().file.clone();
+    for _ in 0..3 {
+        b.step();
+        save_system.autosave(b.to_save(), 1).unwrap();
+        save_system.flush();
+    }
+
+    assert!(save_system.last_error.is_none());
+    assert_eq!(autosave_steps(&save_system, "A"), vec![4, 3]);
+    assert_eq!(autosave_steps(&save_system, "B"), vec![3]);
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+
+    //saves are found by file, however many were deleted around them
+    let loaded = World::from_save(save_system.load(&kept).unwrap());
+    assert_eq!(loaded.current_step(), 4);
+    assert_eq!(loaded.name(), "A");
+}
+
+#[test]
+fn saves_are_written_through_a_temporary_file(){
+    let dir = test_dir("temporary");
+    //left over from a write of the same save that never finished, it is written over and renamed into place this time
+    fs::write(dir.join(".World 42.tmp"), b"half a save").unwrap();
+    let mut save_system = SaveSystem::new(&dir);
+    assert!(save_system.saves.is_empty());
+
+    let world = World::new(SimParams::default());
+    let file = save_system.save(world.to_save()).unwrap();
+    save_system.flush();
+    let again = save_system.save(world.to_save()).unwrap();
+    save_system.flush();
+
+    assert_ne!(file, again);
+    let mut files: Vec<String> = fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name().into_string().unwrap()).collect();
+    files.sort();
+    assert_eq!(files, vec![file.clone(), again]);
+    assert!(save_system.load(&file).is_ok());
+    assert!(matches!(save_system.load(".World 42.tmp"), Err(SaveError::NoSuchSave(_))));
+}
@@ -0,0 +1,99 @@
+use std::thread;
+use std::time::Duration;
+use ecosystem_simulation::utilities::save_system::SaveSystem;
+use ecosystem_simulation::utilities::simulation_parameters::SimParams;
+use ecosystem_simulation::utilities::timeline::Timeline;
+use ecosystem_simulation::world::World;
+
+fn positions(world: &World) -> Vec<[f32;2]>{
+    world.animals().animals.iter().map(|animal| animal.body.position).collect()
+}
+
+//snapshots are encoded on the save thread, so wait for them to come back
+fn wait_for(timeline: &mut Timeline, world: &World, save_system: &SaveSystem, count: usize){
+    for _ in 0..1000 {
+        timeline.update(world, save_system).unwrap();
+        if timeline.snapshots().len() >= count {
+            return;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    panic!("snapshots were never encoded");
+}
+
+#[test]
+fn restoring_a_snapshot_rewinds_the_world_and_its_log(){
+    //nothing is written, so the saves directory is never created
+    let save_system = SaveSystem::new(&std::env::temp_dir().join("ecosystem_simulation_timeline_test"));
+    let mut timeline = Timeline::default();
+    timeline.set_interval(500);
+
+    let mut world = World::new(SimParams::default());
+    let mut reference = World::new(SimParams::default());
+    while world.current_step() < 1200 {
+        timeline.update(&world, &save_system).unwrap();
+        if world.current_step() % 100 == 0 {
+            world.record_stats();
+        }
+        world.step();
+        if reference.current_step() < 1000 {
+            reference.step();
+        }
+    }
+    wait_for(&mut timeline, &world, &save_system, 3);
+
+    let steps: Vec<i32> = timeline.snapshots().iter().map(|snapshot| snapshot.step).collect();
+    assert_eq!(steps, vec![0, 500, 1000]);
+
+    let stats = world.stats().populations.animals.len();
+    assert!(stats > 0);
+    timeline.restore(1, &mut world).unwrap();
+    assert_eq!(world.current_step(), 500);
+    assert!(world.log().events().all(|logged| logged.step < 500));
+    assert_eq!(world.stats().populations.animals.len(), stats);
+    assert_eq!(timeline.snapshots().len(), 2);
+
+    //the rewound world plays out the same as one that was never rewound
+    while world.current_step() < 1000 {
+        world.step();
+    }
+    assert!(world.log().events().eq(reference.log().events()));
+    assert_eq!(positions(&world), positions(&reference));
+}
+
+fn flow_totals(world: &World) -> Vec<f64>{
+    world.stats().energy.energy.iter().map(|series| series.iter().map(|[_, flow]| flow).sum()).collect()
+}
+
+//deaths and flows the snapshot hadn't handed to the stats yet were already counted by the world it was rewound from
+#[test]
+fn rewinding_does_not_count_stats_twice(){
+    let save_system = SaveSystem::new(&std::env::temp_dir().join("ecosystem_simulation_timeline_test"));
+    let mut timeline = Timeline::default();
+    timeline.set_interval(3000);
+
+    //a small world, where animals start dying sooner
+    let mut sim_params = SimParams::default();
+    sim_params.world.width = 40.;
+    sim_params.world.height = 40.;
+    let mut world = World::new(sim_params);
+    while world.current_step() < 6500 {
+        //the snapshot is taken before the stats are recorded on the same step, so it holds a hundred steps of them
+        timeline.update(&world, &save_system).unwrap();
+        if world.current_step() % 100 == 0 {
+            world.record_stats();
+        }
+        world.step();
+    }
+    wait_for(&mut timeline, &world, &save_system, 3);
+    world.record_stats();
+
+    let deaths = world.stats().life_history.total_deaths.clone();
+    let flows = flow_totals(&world);
+    assert!(deaths.iter().sum::<usize>() > 0);
+
+    timeline.restore(1, &mut world).unwrap();
+    world.record_stats();
+    assert_eq!(world.stats().life_history.total_deaths, deaths);
+    assert_eq!(flow_totals(&world), flows);
+}